@@ -124,6 +124,27 @@ impl Mesh {
 		}
 	}
 	
+	pub fn updateData(&mut self, vertices: Vec<Vertex>, indices: Option<Vec<u32>>) -> Result<(), String> {
+		self.checkDestroyed()?;
+		if !self.isUploaded() {
+			return Err("Upload mesh before updating data!".to_string());
+		}
+		if self.ibo.is_some() != indices.is_some() {
+			return Err("Can't change mesh between indexed and non-indexed!".to_string());
+		}
+		
+		unsafe {
+			self.gl.named_buffer_data_u8_slice(self.vboMesh.unwrap(), cast_slice(&vertices), glow::DYNAMIC_DRAW);
+			if let Some(indices) = &indices {
+				self.gl.named_buffer_data_u8_slice(self.ibo.unwrap(), cast_slice(indices), glow::DYNAMIC_DRAW);
+			}
+			gl_check_error!(self.gl);
+		}
+		self.vertices = vertices;
+		self.indices = indices;
+		Ok(())
+	}
+	
 	pub fn upload(&mut self, shader: ShaderRef) -> Result<(), String> {
 		self.checkDestroyed()?;
 		unsafe {
@@ -1,102 +1,15 @@
-use std::f32::consts::TAU;
+use std::f32::consts::PI;
 use bool_flags::Flags8;
-use glam::{vec3, Mat4, Vec3};
-use crate::graphics::{LineRenderer, Renderable};
-use crate::graphics::mesh::{InstanceMeshData, Mesh, Vertex};
+use glam::Vec3;
 use crate::simulation::region::AABB;
+use crate::simulation::shape::{signedArea, Shape};
 use crate::simulation::{solver, Transform};
 use crate::simulation::solver::Physical;
-use crate::types::{newMeshRef, GlRef, MeshRef, ShaderRef, SolverRef};
 
 const F_FIXED: u8 = 0;
 const F_VISIBLE: u8 = 1;
-
-// todo: use shape/collision component
-/// Instance renderable
-pub struct BallRenderable {
-	mesh: MeshRef,
-	shader: ShaderRef,
-	verletSolver: SolverRef,
-}
-
-impl BallRenderable {
-	pub fn new(gl: GlRef, shader: ShaderRef, verletSolver: SolverRef) -> Self {
-		let (vertices, indices) = Self::data();
-		let mesh = Mesh::instance(gl, vertices, Some(indices));
-		Self {
-			mesh: newMeshRef(mesh),
-			shader,
-			verletSolver,
-		}
-	}
-	
-	fn data() -> (Vec<Vertex>, Vec<u32>) {
-		let segments = 20;
-		let mut vertices = Vec::with_capacity(segments + 1);
-		let mut indices = Vec::with_capacity(8 * 3);
-		
-		vertices.push(Default::default());
-		for i in 0..segments {
-			let angle = i as f32 * TAU / segments as f32;
-			vertices.push(Vertex {
-				position: vec3(angle.cos(), angle.sin(), 0.0) / 2.0,
-				..Default::default()
-			});
-			
-			indices.push(0);
-			indices.push(i as u32 + 1);
-			let i2 = i as u32 + 2;
-			if i2 <= segments as u32 {
-				indices.push(i2);
-			} else {
-				indices.push(i2 - segments as u32);
-			}
-		}
-		
-		(vertices, indices)
-	}
-}
-
-impl Renderable for BallRenderable {
-	fn meshRef(&self) -> Option<&MeshRef> {
-		Option::from(&self.mesh)
-	}
-	
-	fn shaderRef(&self) -> Option<&ShaderRef> {
-		Option::from(&self.shader)
-	}
-	
-	fn render(&self, projViewMat: &Mat4, _dt: f32, _lineRenderer: &mut LineRenderer) -> Result<(), String> {
-		if let Some(mesh) = self.meshRef() && let Some(shader) = self.shaderRef() {
-			let mut mesh = mesh.borrow_mut();
-			let shader = shader.read().unwrap();
-			
-			shader.bind();
-			let pvm = projViewMat * self.modelMatrix();
-			shader.setMatrix4f("u_pvm", &pvm);
-			
-			let data: Vec<InstanceMeshData> = self.verletSolver.borrow()
-												  .getPhysicals().iter()
-												  .map(|(_, physical)| {
-													  let physical = physical.read().unwrap();
-													  InstanceMeshData {
-														  matrix: physical.transform().getModelMatrix(),
-														  color: physical.color().to_homogeneous(),
-													  }
-												  }).collect();
-			mesh.updateInstanceData(&data)?;
-			
-			mesh.draw();
-		}
-		Ok(())
-	}
-}
-
-impl Drop for BallRenderable {
-	fn drop(&mut self) {
-		self.destroy();
-	}
-}
+const F_SLEEPING: u8 = 2;
+const F_FLUID: u8 = 3;
 
 /// Physics object
 #[derive(Debug)]
@@ -106,35 +19,58 @@ pub struct Ball {
 	pub lastTransform: Transform,
 	pub acceleration: Vec3,
 	pub elasticity: f32,
+	pub mass: f32,
 	pub color: Vec3,
+	pub shape: Shape,
+	/// Collision layer bits, see `Physical::layer`
+	pub layer: u32,
+	/// Layers collided with, see `Physical::mask`
+	pub mask: u32,
+	restTime: f32,
+	lifetime: Option<f32>,
 	flags: Flags8,
 	aabb: AABB,
 }
 
 impl Ball {
 	pub fn new(pos: Vec3, size: Vec3) -> Self {
+		Self::withShape(pos, size, Shape::Circle)
+	}
+	
+	/// Set the mass from a density and the shape's area
+	pub fn setDensity(&mut self, density: f32) {
+		let area = if self.shape.isCircle() {
+			let radius = self.shape.radius(&self.transform);
+			PI * radius * radius
+		} else {
+			signedArea(&self.shape.vertices(&self.transform))
+		};
+		self.mass = density * area;
+	}
+	
+	pub fn withShape(pos: Vec3, size: Vec3, shape: Shape) -> Self {
 		let mut flags = Flags8::none();
 		flags.set(F_VISIBLE);
+		let transform = Transform {
+			position: pos,
+			scale: size,
+			..Default::default()
+		};
 		Self {
 			id: solver::newId(),
-			transform: Transform {
-				position: pos,
-				scale: size,
-				..Default::default()
-			},
-			lastTransform: Transform {
-				position: pos,
-				scale: size,
-				..Default::default()
-			},
+			transform,
+			lastTransform: transform,
 			acceleration: Vec3::ZERO,
 			elasticity: 1.0,
+			mass: 1.0,
 			color: Vec3::ONE,
+			aabb: shape.bounds(&transform),
+			shape,
+			layer: 1,
+			mask: u32::MAX,
+			restTime: 0.0,
+			lifetime: None,
 			flags,
-			aabb: AABB {
-				position: pos - size / 2.0,
-				size,
-			}
 		}
 	}
 }
@@ -164,8 +100,65 @@ impl Physical for Ball {
 		self.flags.get(F_FIXED)
 	}
 	
+	fn setFixed(&mut self, fixed: bool) {
+		if fixed {
+			self.flags.set(F_FIXED);
+		} else {
+			self.flags.clear(F_FIXED);
+		}
+		// Start from rest either way
+		self.lastTransform.position = self.transform.position;
+		self.acceleration = Vec3::ZERO;
+	}
+	
+	fn sleeping(&self) -> bool {
+		self.flags.get(F_SLEEPING)
+	}
+	
+	fn setSleeping(&mut self, sleeping: bool) {
+		if sleeping {
+			self.flags.set(F_SLEEPING);
+			self.lastTransform.position = self.transform.position;
+			self.acceleration = Vec3::ZERO;
+		} else {
+			self.flags.clear(F_SLEEPING);
+		}
+		self.restTime = 0.0;
+	}
+	
+	fn restTime(&self) -> f32 {
+		self.restTime
+	}
+	
+	fn setRestTime(&mut self, time: f32) {
+		self.restTime = time;
+	}
+	
+	fn lifetime(&self) -> Option<f32> {
+		self.lifetime
+	}
+	
+	fn setLifetime(&mut self, lifetime: Option<f32>) {
+		self.lifetime = lifetime;
+	}
+	
+	fn fluid(&self) -> bool {
+		self.flags.get(F_FLUID)
+	}
+	
+	fn setFluid(&mut self, fluid: bool) {
+		if fluid {
+			self.flags.set(F_FLUID);
+		} else {
+			self.flags.clear(F_FLUID);
+		}
+	}
+	
 	fn update(&mut self, dt: f32) {
 		if self.fixed() {
+			// Pinned bodies can still be moved by hand, keep bounds current and velocity zero
+			self.lastTransform = self.transform;
+			self.aabb = self.shape.bounds(&self.transform);
 			return;
 		}
 		let delta = self.transform.position - self.lastTransform.position;
@@ -173,14 +166,16 @@ impl Physical for Ball {
 		self.transform.position += delta + self.acceleration * dt * dt;
 		self.acceleration = Vec3::ZERO;
 		
-		self.aabb.position = self.transform.position - self.transform.scale / 2.0;
-		self.aabb.size = self.transform.scale;
+		self.aabb = self.shape.bounds(&self.transform);
 	}
 	
 	fn accelerate(&mut self, acceleration: Vec3) {
 		if self.fixed() {
 			return;
 		}
+		if self.sleeping() {
+			self.setSleeping(false);
+		}
 		self.acceleration += acceleration;
 	}
 	
@@ -188,6 +183,9 @@ impl Physical for Ball {
 		if self.fixed() {
 			return;
 		}
+		if self.sleeping() {
+			self.setSleeping(false);
+		}
 		self.lastTransform.position = self.transform.position - velocity * dt;
 	}
 	
@@ -195,6 +193,9 @@ impl Physical for Ball {
 		if self.fixed() {
 			return;
 		}
+		if self.sleeping() {
+			self.setSleeping(false);
+		}
 		self.lastTransform.position -= velocity * dt;
 	}
 	
@@ -202,14 +203,30 @@ impl Physical for Ball {
 		(self.transform.position - self.lastTransform.position) / dt
 	}
 	
+	fn layer(&self) -> u32 {
+		self.layer
+	}
+	
+	fn mask(&self) -> u32 {
+		self.mask
+	}
+	
 	fn elasticity(&self) -> f32 {
 		self.elasticity
 	}
 	
+	fn mass(&self) -> f32 {
+		self.mass
+	}
+	
 	fn color(&self) -> Vec3 {
 		self.color
 	}
 	
+	fn shape(&self) -> &Shape {
+		&self.shape
+	}
+	
 	fn bounds(&self) -> AABB {
 		self.aabb
 	}
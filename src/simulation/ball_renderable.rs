@@ -0,0 +1,94 @@
+use std::f32::consts::TAU;
+use glam::{vec3, Mat4};
+use crate::graphics::{LineRenderer, Renderable};
+use crate::graphics::mesh::{InstanceMeshData, Mesh, Vertex};
+use crate::types::{newMeshRef, GlRef, MeshRef, ShaderRef, SolverRef};
+
+/// Instance renderable for circle shapes
+pub struct BallRenderable {
+	mesh: MeshRef,
+	shader: ShaderRef,
+	verletSolver: SolverRef,
+}
+
+impl BallRenderable {
+	pub fn new(gl: GlRef, shader: ShaderRef, verletSolver: SolverRef) -> Self {
+		let (vertices, indices) = Self::data();
+		let mesh = Mesh::instance(gl, vertices, Some(indices));
+		Self {
+			mesh: newMeshRef(mesh),
+			shader,
+			verletSolver,
+		}
+	}
+	
+	fn data() -> (Vec<Vertex>, Vec<u32>) {
+		let segments = 20;
+		let mut vertices = Vec::with_capacity(segments + 1);
+		let mut indices = Vec::with_capacity(8 * 3);
+		
+		vertices.push(Default::default());
+		for i in 0..segments {
+			let angle = i as f32 * TAU / segments as f32;
+			vertices.push(Vertex {
+				position: vec3(angle.cos(), angle.sin(), 0.0) / 2.0,
+				..Default::default()
+			});
+			
+			indices.push(0);
+			indices.push(i as u32 + 1);
+			let i2 = i as u32 + 2;
+			if i2 <= segments as u32 {
+				indices.push(i2);
+			} else {
+				indices.push(i2 - segments as u32);
+			}
+		}
+		
+		(vertices, indices)
+	}
+}
+
+impl Renderable for BallRenderable {
+	fn meshRef(&self) -> Option<&MeshRef> {
+		Option::from(&self.mesh)
+	}
+	
+	fn shaderRef(&self) -> Option<&ShaderRef> {
+		Option::from(&self.shader)
+	}
+	
+	fn render(&self, projViewMat: &Mat4, _dt: f32, _lineRenderer: &mut LineRenderer) -> Result<(), String> {
+		if let Some(mesh) = self.meshRef() && let Some(shader) = self.shaderRef() {
+			let mut mesh = mesh.borrow_mut();
+			let shader = shader.read().unwrap();
+			
+			shader.bind();
+			let pvm = projViewMat * self.modelMatrix();
+			shader.setMatrix4f("u_pvm", &pvm);
+			
+			let data: Vec<InstanceMeshData> = self.verletSolver.borrow()
+												  .getPhysicals().iter()
+												  .filter_map(|(_, physical)| {
+													  let physical = physical.read().unwrap();
+													  if !physical.shape().isCircle() {
+														  return None;
+													  }
+													  Some(InstanceMeshData {
+														  matrix: physical.transform().getModelMatrix(),
+														  color: physical.color().to_homogeneous(),
+													  })
+												  }).collect();
+			mesh.updateInstanceData(&data)?;
+			
+			mesh.draw();
+		}
+		Ok(())
+	}
+}
+
+impl Drop for BallRenderable {
+	fn drop(&mut self) {
+		self.destroy();
+	}
+}
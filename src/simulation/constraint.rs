@@ -0,0 +1,283 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::{vec3, Vec3};
+use crate::simulation::shape::{edgeNormal, signedArea};
+use crate::simulation::solver;
+use crate::types::PhysicalRef;
+
+pub trait Constraint: Any + Debug + Send + Sync {
+	fn id(&self) -> usize;
+	
+	fn name(&self) -> &str;
+	
+	/// Pairs of physical ids linked by this constraint, used for drawing and cleanup
+	fn links(&self) -> Vec<(usize, usize)>;
+	
+	fn solve(&mut self, physicals: &BTreeMap<usize, PhysicalRef>, dt: f32);
+	
+	fn color(&self) -> Vec3;
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui);
+}
+
+/// Link holding two physicals at a fixed distance
+#[derive(Debug)]
+pub struct DistanceConstraint {
+	id: usize,
+	pub physical1: usize,
+	pub physical2: usize,
+	pub length: f32,
+	/// Share of the error corrected per solve, 1 is rigid
+	pub stiffness: f32,
+	pub color: Vec3,
+}
+
+impl DistanceConstraint {
+	pub fn new(physical1: usize, physical2: usize, length: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			physical1,
+			physical2,
+			length,
+			stiffness: 1.0,
+			color: Vec3::ONE,
+		}
+	}
+	
+	/// Link two physicals at their current distance
+	pub fn between(physical1: &PhysicalRef, physical2: &PhysicalRef) -> Self {
+		let physical1 = physical1.read().unwrap();
+		let physical2 = physical2.read().unwrap();
+		let length = physical1.transform().position.distance(physical2.transform().position);
+		Self::new(physical1.id(), physical2.id(), length)
+	}
+}
+
+impl Constraint for DistanceConstraint {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Distance"
+	}
+	
+	fn links(&self) -> Vec<(usize, usize)> {
+		vec![(self.physical1, self.physical2)]
+	}
+	
+	fn solve(&mut self, physicals: &BTreeMap<usize, PhysicalRef>, _dt: f32) {
+		if self.physical1 == self.physical2 {
+			return;
+		}
+		let (Some(physical1), Some(physical2)) = (physicals.get(&self.physical1), physicals.get(&self.physical2)) else {
+			return;
+		};
+		let mut physical1 = physical1.write().unwrap();
+		let mut physical2 = physical2.write().unwrap();
+		
+		let weight1 = physical1.inverseMass();
+		let weight2 = physical2.inverseMass();
+		let weightSum = weight1 + weight2;
+		if weightSum <= 0.0 {
+			return;
+		}
+		
+		let dir = physical1.transform().position - physical2.transform().position;
+		let dist = dir.length();
+		if dist <= f32::EPSILON {
+			return;
+		}
+		
+		let correction = dir * ((dist - self.length) / dist / weightSum * self.stiffness);
+		physical1.transformMut().position -= correction * weight1;
+		physical2.transformMut().position += correction * weight2;
+	}
+	
+	fn color(&self) -> Vec3 {
+		self.color
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float("Length", &mut self.length);
+		ui.slider_f32("Stiffness", &mut self.stiffness, 0.0, 1.0);
+		self.length = self.length.max(0.0);
+	}
+}
+
+/// Damped spring pulling two physicals towards a rest length
+#[derive(Debug)]
+pub struct SpringConstraint {
+	id: usize,
+	pub physical1: usize,
+	pub physical2: usize,
+	pub restLength: f32,
+	pub stiffness: f32,
+	pub damping: f32,
+	pub color: Vec3,
+}
+
+impl SpringConstraint {
+	pub fn new(physical1: usize, physical2: usize, restLength: f32, stiffness: f32, damping: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			physical1,
+			physical2,
+			restLength,
+			stiffness,
+			damping,
+			color: vec3(1.0, 0.5, 0.0),
+		}
+	}
+	
+	/// Spring between two physicals resting at their current distance
+	pub fn between(physical1: &PhysicalRef, physical2: &PhysicalRef, stiffness: f32, damping: f32) -> Self {
+		let physical1 = physical1.read().unwrap();
+		let physical2 = physical2.read().unwrap();
+		let restLength = physical1.transform().position.distance(physical2.transform().position);
+		Self::new(physical1.id(), physical2.id(), restLength, stiffness, damping)
+	}
+}
+
+impl Constraint for SpringConstraint {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Spring"
+	}
+	
+	fn links(&self) -> Vec<(usize, usize)> {
+		vec![(self.physical1, self.physical2)]
+	}
+	
+	fn solve(&mut self, physicals: &BTreeMap<usize, PhysicalRef>, dt: f32) {
+		if self.physical1 == self.physical2 {
+			return;
+		}
+		let (Some(physical1), Some(physical2)) = (physicals.get(&self.physical1), physicals.get(&self.physical2)) else {
+			return;
+		};
+		let mut physical1 = physical1.write().unwrap();
+		let mut physical2 = physical2.write().unwrap();
+		// Forces wake bodies, a spring at rest shouldn't
+		if physical1.sleeping() && physical2.sleeping() {
+			return;
+		}
+		
+		let dir = physical1.transform().position - physical2.transform().position;
+		let dist = dir.length();
+		if dist <= f32::EPSILON {
+			return;
+		}
+		let normal = dir / dist;
+		
+		// Hooke's law along the spring plus damping of the relative verlet velocity
+		let relativeVelocity = (physical1.getVelocity(dt) - physical2.getVelocity(dt)).dot(normal);
+		let force = normal * (-self.stiffness * (dist - self.restLength) - self.damping * relativeVelocity);
+		
+		physical1.applyForce(force);
+		physical2.applyForce(-force);
+	}
+	
+	fn color(&self) -> Vec3 {
+		self.color
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float("Rest length", &mut self.restLength);
+		ui.input_float("Stiffness", &mut self.stiffness);
+		ui.input_float("Damping", &mut self.damping);
+		self.restLength = self.restLength.max(0.0);
+		self.stiffness = self.stiffness.max(0.0);
+		self.damping = self.damping.max(0.0);
+	}
+}
+
+/// Gas pressure inside a closed ring of physicals, pushing out when squeezed below its rest area and pulling in above it
+#[derive(Debug)]
+pub struct PressureConstraint {
+	id: usize,
+	/// Ring in order, either winding works
+	pub physicals: Vec<usize>,
+	pub restArea: f32,
+	/// Force per unit of edge length when the area is halved
+	pub pressure: f32,
+	pub color: Vec3,
+}
+
+impl PressureConstraint {
+	pub fn new(physicals: Vec<usize>, restArea: f32, pressure: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			physicals,
+			restArea,
+			pressure,
+			color: vec3(0.0, 0.5, 1.0),
+		}
+	}
+	
+	/// Pressure keeping the ring's current area
+	pub fn around(physicals: &[PhysicalRef], pressure: f32) -> Self {
+		let positions: Vec<Vec3> = physicals.iter().map(|physical| physical.read().unwrap().transform().position).collect();
+		let ids = physicals.iter().map(|physical| physical.read().unwrap().id()).collect();
+		Self::new(ids, signedArea(&positions).abs(), pressure)
+	}
+}
+
+impl Constraint for PressureConstraint {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Pressure"
+	}
+	
+	fn links(&self) -> Vec<(usize, usize)> {
+		(0..self.physicals.len()).map(|i| (self.physicals[i], self.physicals[(i + 1) % self.physicals.len()])).collect()
+	}
+	
+	fn solve(&mut self, physicals: &BTreeMap<usize, PhysicalRef>, _dt: f32) {
+		let Some(ring) = self.physicals.iter().map(|id| physicals.get(id)).collect::<Option<Vec<_>>>() else {
+			return;
+		};
+		if ring.len() < 3 || ring.iter().all(|physical| physical.read().unwrap().sleeping()) {
+			return;
+		}
+		let positions: Vec<Vec3> = ring.iter().map(|physical| physical.read().unwrap().transform().position).collect();
+		let area = signedArea(&positions);
+		if area.abs() <= f32::EPSILON {
+			return;
+		}
+		
+		// Ideal gas against an ambient pressure that balances it at the rest area
+		let pressure = self.pressure * (self.restArea / area.abs() - 1.0);
+		let winding = area.signum();
+		for i in 0..ring.len() {
+			let j = (i + 1) % ring.len();
+			let (a, b) = (positions[i], positions[j]);
+			let force = edgeNormal(a, b) * (winding * pressure * a.distance(b) / 2.0);
+			ring[i].write().unwrap().applyForce(force);
+			ring[j].write().unwrap().applyForce(force);
+		}
+	}
+	
+	fn color(&self) -> Vec3 {
+		self.color
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float("Rest area", &mut self.restArea);
+		ui.input_float("Pressure", &mut self.pressure);
+		self.restArea = self.restArea.max(0.0);
+	}
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use glam::Vec3;
+use crate::simulation::shape::{closestPointOnSegment, edgeNormal, growPolygon, polygonContainsPoint, polygonPolygon, signedArea};
+use crate::simulation::solver::{self, Solver};
+use crate::types::PhysicalRef;
+
+/// Box body made from four corner particles held together by distance constraints
+#[derive(Debug)]
+pub struct ConstraintBox {
+	id: usize,
+	pub corners: [usize; 4],
+	pub constraints: Vec<usize>,
+	pub color: Vec3,
+}
+
+impl ConstraintBox {
+	pub fn new(corners: [usize; 4], constraints: Vec<usize>) -> Self {
+		Self {
+			id: solver::newId(),
+			corners,
+			constraints,
+			color: Vec3::ONE,
+		}
+	}
+	
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	
+	pub fn hasCorner(&self, id: usize) -> bool {
+		self.corners.contains(&id)
+	}
+	
+	/// Corner ids and positions in counter-clockwise order, `None` if a corner is missing
+	fn ordered(&self, physicals: &BTreeMap<usize, PhysicalRef>) -> Option<([usize; 4], Vec<Vec3>)> {
+		let mut corners = self.corners;
+		let mut polygon = Vec::with_capacity(4);
+		for id in corners.iter() {
+			polygon.push(physicals.get(id)?.read().unwrap().transform().position);
+		}
+		if signedArea(&polygon) < 0.0 {
+			corners.reverse();
+			polygon.reverse();
+		}
+		Some((corners, polygon))
+	}
+	
+	pub fn polygon(&self, physicals: &BTreeMap<usize, PhysicalRef>) -> Option<Vec<Vec3>> {
+		self.ordered(physicals).map(|(_, polygon)| polygon)
+	}
+	
+	/// Outline padded by the corner radii, which is where the box touches other boxes
+	fn outline(&self, physicals: &BTreeMap<usize, PhysicalRef>) -> Option<Vec<Vec3>> {
+		let mut padding = 0.0f32;
+		for id in self.corners.iter() {
+			let corner = physicals.get(id)?.read().unwrap();
+			padding = padding.max(corner.shape().radius(corner.transform()));
+		}
+		self.polygon(physicals).map(|polygon| growPolygon(&polygon, padding))
+	}
+	
+	/// Separate two boxes along the axis of least overlap, moving the corners of each box as one, then
+	/// cancel their sliding along the contact. Resolving corner by corner against the closest edge pushes
+	/// deep corners out through the far side
+	pub fn collideBox(&self, other: &ConstraintBox, physicals: &BTreeMap<usize, PhysicalRef>) {
+		let (Some(outline1), Some(outline2)) = (self.outline(physicals), other.outline(physicals)) else {
+			return;
+		};
+		for id1 in self.corners.iter() {
+			let corner1 = physicals[id1].read().unwrap();
+			if other.corners.iter().any(|id2| !corner1.collidesWith(&*physicals[id2].read().unwrap())) {
+				return;
+			}
+		}
+		let Some(contact) = polygonPolygon(&outline1, &outline2) else {
+			return;
+		};
+		
+		// A moving box wakes a sleeping one it runs into
+		let restless = |corners: &[usize; 4]| corners.iter().any(|id| Solver::restless(&*physicals[id].read().unwrap()));
+		for (corners, woken) in [(&self.corners, restless(&other.corners)), (&other.corners, restless(&self.corners))] {
+			if woken {
+				for id in corners.iter() {
+					physicals[id].write().unwrap().setSleeping(false);
+				}
+			}
+		}
+		
+		// Each box weighs in with the mean inverse mass of its awake corners, its motion is the mean corner motion
+		let state = |corners: &[usize; 4]| {
+			let mut weight = 0.0;
+			let mut motion = Vec3::ZERO;
+			for id in corners.iter() {
+				let corner = physicals[id].read().unwrap();
+				if !corner.sleeping() {
+					weight += corner.inverseMass();
+				}
+				motion += corner.transform().position - corner.lastTransform().position;
+			}
+			(weight / corners.len() as f32, motion / corners.len() as f32)
+		};
+		let ((weight1, motion1), (weight2, motion2)) = (state(&self.corners), state(&other.corners));
+		if weight1 + weight2 <= f32::EPSILON {
+			return;
+		}
+		
+		let sliding = motion1 - motion2;
+		let sliding = sliding - contact.normal * sliding.dot(contact.normal);
+		let correction = (contact.normal * contact.depth - sliding) / (weight1 + weight2);
+		for (corners, offset) in [(&self.corners, correction * weight1), (&other.corners, -correction * weight2)] {
+			for id in corners.iter() {
+				let mut corner = physicals[id].write().unwrap();
+				if corner.inverseMass() > 0.0 && !corner.sleeping() {
+					corner.transformMut().position += offset;
+				}
+			}
+		}
+	}
+	
+	/// Push a physical out of the box through the closest edge, moving the edge's corners back.
+	/// Skipped unless every corner collides with the physical by layer and mask
+	pub fn collidePhysical(&self, physicals: &BTreeMap<usize, PhysicalRef>, physical: &PhysicalRef) {
+		let Some((corners, polygon)) = self.ordered(physicals) else {
+			return;
+		};
+		
+		let points = {
+			let physical = physical.read().unwrap();
+			if corners.iter().any(|id| !physicals[id].read().unwrap().collidesWith(&*physical)) {
+				return;
+			}
+			
+			let shape = physical.shape();
+			if shape.isCircle() {
+				vec![(physical.transform().position, shape.radius(physical.transform()))]
+			} else {
+				shape.vertices(physical.transform()).into_iter().map(|vertex| (vertex, 0.0)).collect()
+			}
+		};
+		
+		for (point, radius) in points {
+			let inside = polygonContainsPoint(&polygon, point);
+			
+			// Closest edge to the point
+			let mut edge = 0;
+			let mut closest = polygon[0];
+			let mut closestDist = f32::MAX;
+			for i in 0..polygon.len() {
+				let q = closestPointOnSegment(polygon[i], polygon[(i + 1) % polygon.len()], point);
+				let dist = q.distance(point);
+				if dist < closestDist {
+					closestDist = dist;
+					closest = q;
+					edge = i;
+				}
+			}
+			
+			let a = polygon[edge];
+			let b = polygon[(edge + 1) % polygon.len()];
+			let (normal, depth) = if inside {
+				(edgeNormal(a, b), closestDist + radius)
+			} else if closestDist < radius && closestDist > f32::EPSILON {
+				((point - closest) / closestDist, radius - closestDist)
+			} else {
+				continue;
+			};
+			
+			let ab = b - a;
+			let t = if ab.length_squared() > f32::EPSILON { ((closest - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0) } else { 0.0 };
+			
+			let mut physical = physical.write().unwrap();
+			let mut cornerA = physicals[&corners[edge]].write().unwrap();
+			let mut cornerB = physicals[&corners[(edge + 1) % corners.len()]].write().unwrap();
+			
+			// Corners share the correction by how close the contact is to each of them
+			let weight = physical.inverseMass();
+			let weightA = cornerA.inverseMass() * (1.0 - t);
+			let weightB = cornerB.inverseMass() * t;
+			let weightSum = weight + weightA * (1.0 - t) + weightB * t;
+			if weightSum <= f32::EPSILON {
+				continue;
+			}
+			
+			let correction = normal * (depth / weightSum);
+			physical.transformMut().position += correction * weight;
+			cornerA.transformMut().position -= correction * weightA;
+			cornerB.transformMut().position -= correction * weightB;
+		}
+	}
+}
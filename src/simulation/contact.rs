@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use glam::Vec3;
+
+/// Two touching physicals, `normal` points from `id2` towards `id1`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PhysicalContact {
+	pub id1: usize,
+	pub id2: usize,
+	pub point: Vec3,
+	pub normal: Vec3,
+	pub depth: f32,
+}
+
+impl PhysicalContact {
+	/// Same contact with the lower id first, so each pair has one key
+	pub fn ordered(self) -> Self {
+		if self.id1 <= self.id2 {
+			self
+		} else {
+			Self {
+				id1: self.id2,
+				id2: self.id1,
+				normal: -self.normal,
+				..self
+			}
+		}
+	}
+	
+	pub fn key(&self) -> (usize, usize) {
+		(self.id1.min(self.id2), self.id1.max(self.id2))
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ContactEvent {
+	/// Pair started touching this update
+	Begin(PhysicalContact),
+	/// Pair was already touching last update
+	Persist(PhysicalContact),
+	/// Pair stopped touching, carries the last contact seen
+	End(PhysicalContact),
+}
+
+impl ContactEvent {
+	pub fn contact(&self) -> &PhysicalContact {
+		match self {
+			ContactEvent::Begin(contact) | ContactEvent::Persist(contact) | ContactEvent::End(contact) => contact,
+		}
+	}
+	
+	pub fn name(&self) -> &str {
+		match self {
+			ContactEvent::Begin(_) => "Begin",
+			ContactEvent::Persist(_) => "Persist",
+			ContactEvent::End(_) => "End",
+		}
+	}
+}
+
+pub type ContactListener = Box<dyn FnMut(&ContactEvent) + Send>;
+
+/// Turns the contacts found during one update into events by comparing against the pairs touching last update
+pub fn diffContacts<F: Fn(&PhysicalContact) -> bool>(touching: &mut HashMap<(usize, usize), PhysicalContact>, found: Vec<PhysicalContact>, keep: F) -> Vec<ContactEvent> {
+	// Sub steps and both visiting orders report the same pair, the deepest one wins
+	let mut current: HashMap<(usize, usize), PhysicalContact> = HashMap::with_capacity(found.len());
+	for contact in found {
+		let contact = contact.ordered();
+		match current.get(&contact.key()) {
+			Some(deepest) if deepest.depth >= contact.depth => {},
+			_ => {
+				current.insert(contact.key(), contact);
+			},
+		}
+	}
+	
+	let mut events = Vec::with_capacity(current.len());
+	for (key, contact) in touching.iter() {
+		if !current.contains_key(key) {
+			if keep(contact) {
+				current.insert(*key, *contact);
+			} else {
+				events.push(ContactEvent::End(*contact));
+			}
+		}
+	}
+	for (key, contact) in current.iter() {
+		if touching.contains_key(key) {
+			events.push(ContactEvent::Persist(*contact));
+		} else {
+			events.push(ContactEvent::Begin(*contact));
+		}
+	}
+	*touching = current;
+	
+	// Map order is random, keep listeners deterministic
+	events.sort_by_key(|event| event.contact().key());
+	events
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use glam::Vec3;
+	use super::{diffContacts, ContactEvent, PhysicalContact};
+	
+	#[test]
+	fn contactEventsKeepTheDeepestContact() {
+		let contact = |depth| PhysicalContact { id1: 2, id2: 1, point: Vec3::ZERO, normal: Vec3::X, depth };
+		let mut touching = HashMap::new();
+		let events = diffContacts(&mut touching, vec![contact(1.0), contact(3.0), contact(2.0)], |_| false);
+		assert_eq!(events, vec![ContactEvent::Begin(contact(3.0).ordered())]);
+	}
+}
@@ -0,0 +1,271 @@
+use std::f32::consts::{PI, TAU};
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::{vec3, Vec3};
+use crate::simulation::shape::{closestPointOnSegment, edgeNormal, signedArea};
+use crate::simulation::Physical;
+
+const CURVE_SEGMENTS: usize = 64;
+#[cfg(feature = "graphics")]
+const NAMES: [&str; 5] = ["Box", "Circle", "Capsule", "Polygon", "None"];
+
+/// Boundary keeping physicals in the world, centered on the origin and expected to fit inside `worldSize`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Container {
+	/// Axis aligned box the size of the world
+	Box,
+	Circle {
+		radius: f32,
+	},
+	/// Horizontal segment `2 * halfLength` long grown by `radius`
+	Capsule {
+		halfLength: f32,
+		radius: f32,
+	},
+	/// Convex polygon
+	Polygon(Vec<Vec3>),
+	/// Physicals are free to leave the world
+	None,
+}
+
+impl Container {
+	pub fn regularPolygon(sides: usize, radius: f32) -> Self {
+		let sides = sides.max(3);
+		Container::Polygon((0..sides).map(|i| {
+			let angle = i as f32 * TAU / sides as f32;
+			vec3(angle.cos(), angle.sin(), 0.0) * radius
+		}).collect())
+	}
+	
+	pub fn name(&self) -> &str {
+		match self {
+			Container::Box => "Box",
+			Container::Circle { .. } => "Circle",
+			Container::Capsule { .. } => "Capsule",
+			Container::Polygon(_) => "Polygon",
+			Container::None => "None",
+		}
+	}
+	
+	/// Kind picker plus its dimensions, new kinds start sized to fit `worldSize`
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui, worldSize: Vec3) {
+		let mut index = NAMES.iter().position(|name| *name == self.name()).unwrap_or(0);
+		if ui.combo_simple_string("Shape", &mut index, &NAMES) {
+			let radius = worldSize.x.min(worldSize.y) / 2.0;
+			*self = match NAMES[index] {
+				"Circle" => Container::Circle { radius },
+				"Capsule" => Container::Capsule {
+					halfLength: (worldSize.x / 2.0 - radius).max(0.0),
+					radius,
+				},
+				"Polygon" => Container::regularPolygon(6, radius),
+				"None" => Container::None,
+				_ => Container::Box,
+			};
+		}
+		
+		match self {
+			Container::Circle { radius } => {
+				ui.input_float("Radius", radius);
+				*radius = radius.max(0.0);
+			},
+			Container::Capsule { halfLength, radius } => {
+				ui.input_float("Half length", halfLength);
+				ui.input_float("Radius", radius);
+				*halfLength = halfLength.max(0.0);
+				*radius = radius.max(0.0);
+			},
+			Container::Polygon(vertices) => {
+				let mut sides = vertices.len() as i32;
+				let mut radius = vertices.first().map_or(0.0, |vertex| vertex.length());
+				let changed = ui.input_int("Sides", &mut sides) | ui.input_float("Radius", &mut radius);
+				if changed {
+					*self = Container::regularPolygon(sides.max(3) as usize, radius.max(0.0));
+				}
+			},
+			Container::Box | Container::None => {},
+		}
+	}
+	
+	/// Counter-clockwise outline, empty without a boundary
+	pub fn outline(&self, worldSize: Vec3) -> Vec<Vec3> {
+		match self {
+			Container::Box => {
+				let half = worldSize / 2.0;
+				vec![
+					vec3(-half.x, -half.y, 0.0),
+					vec3(half.x, -half.y, 0.0),
+					vec3(half.x, half.y, 0.0),
+					vec3(-half.x, half.y, 0.0),
+				]
+			},
+			Container::Circle { radius } => (0..CURVE_SEGMENTS).map(|i| {
+				let angle = i as f32 * TAU / CURVE_SEGMENTS as f32;
+				vec3(angle.cos(), angle.sin(), 0.0) * *radius
+			}).collect(),
+			Container::Capsule { halfLength, radius } => {
+				// Right cap from bottom to top, then the left cap from top to bottom
+				let segments = CURVE_SEGMENTS / 2;
+				let mut outline = Vec::with_capacity((segments + 1) * 2);
+				for (center, start) in [(vec3(*halfLength, 0.0, 0.0), -PI / 2.0), (vec3(-*halfLength, 0.0, 0.0), PI / 2.0)] {
+					for i in 0..=segments {
+						let angle = start + i as f32 * PI / segments as f32;
+						outline.push(center + vec3(angle.cos(), angle.sin(), 0.0) * *radius);
+					}
+				}
+				outline
+			},
+			Container::Polygon(vertices) => {
+				let mut outline = vertices.clone();
+				if signedArea(&outline) < 0.0 {
+					outline.reverse();
+				}
+				outline
+			},
+			Container::None => Vec::new(),
+		}
+	}
+	
+	/// Signed distance from the boundary, positive outside, with the outward normal
+	fn distance(&self, point: Vec3) -> Option<(f32, Vec3)> {
+		match self {
+			Container::Circle { radius } => {
+				let distance = point.length();
+				let normal = if distance > f32::EPSILON { point / distance } else { Vec3::Y };
+				Some((distance - radius, normal))
+			},
+			Container::Capsule { halfLength, radius } => {
+				let closest = closestPointOnSegment(vec3(-*halfLength, 0.0, 0.0), vec3(*halfLength, 0.0, 0.0), point);
+				let offset = point - closest;
+				let distance = offset.length();
+				let normal = if distance > f32::EPSILON { offset / distance } else { Vec3::Y };
+				Some((distance - radius, normal))
+			},
+			Container::Polygon(_) => {
+				let vertices = self.outline(Vec3::ZERO);
+				if vertices.len() < 3 {
+					return None;
+				}
+				
+				let mut maxSeparation = f32::MIN;
+				let mut faceNormal = Vec3::Y;
+				for i in 0..vertices.len() {
+					let a = vertices[i];
+					let normal = edgeNormal(a, vertices[(i + 1) % vertices.len()]);
+					let separation = normal.dot(point - a);
+					if separation > maxSeparation {
+						maxSeparation = separation;
+						faceNormal = normal;
+					}
+				}
+				if maxSeparation <= 0.0 {
+					return Some((maxSeparation, faceNormal));
+				}
+				
+				// Outside, the closest edge point gives the true distance around corners
+				let mut closest = vertices[0];
+				for i in 0..vertices.len() {
+					let candidate = closestPointOnSegment(vertices[i], vertices[(i + 1) % vertices.len()], point);
+					if candidate.distance_squared(point) < closest.distance_squared(point) {
+						closest = candidate;
+					}
+				}
+				let distance = point.distance(closest);
+				let normal = if distance > f32::EPSILON { (point - closest) / distance } else { faceNormal };
+				Some((distance, normal))
+			},
+			Container::Box | Container::None => None,
+		}
+	}
+	
+	/// Sweep a circle of `radius` from inside along a unit `direction` until it touches the outline.
+	/// Returns the distance travelled and the inward normal, nothing when it starts against or outside the boundary
+	pub fn cast(&self, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32, worldSize: Vec3) -> Option<(f32, Vec3)> {
+		let outline = self.outline(worldSize);
+		if outline.len() < 3 {
+			return None;
+		}
+		
+		// Leaving a convex outline means crossing the first edge plane it heads out through
+		let mut closest: Option<(f32, Vec3)> = None;
+		for i in 0..outline.len() {
+			let a = outline[i];
+			let normal = edgeNormal(a, outline[(i + 1) % outline.len()]);
+			let separation = normal.dot(origin - a) + radius;
+			if separation > 0.0 {
+				return None;
+			}
+			let speed = normal.dot(direction);
+			if speed <= 0.0 {
+				continue;
+			}
+			let distance = -separation / speed;
+			if distance <= maxDistance && closest.is_none_or(|(closest, _)| distance < closest) {
+				closest = Some((distance, -normal));
+			}
+		}
+		closest
+	}
+	
+	/// Push a physical back inside, reflecting its verlet velocity scaled by elasticity
+	pub fn collide(&self, physical: &mut dyn Physical, worldSize: Vec3) {
+		if physical.inverseMass() <= 0.0 {
+			return;
+		}
+		match self {
+			Container::None => {},
+			Container::Box => Self::collideBox(physical, worldSize),
+			_ => self.collideCurved(physical),
+		}
+	}
+	
+	fn collideBox(physical: &mut dyn Physical, worldSize: Vec3) {
+		let halfSize = worldSize / 2.0;
+		let bounds = physical.shape().bounds(physical.transform());
+		let (start, end) = (bounds.start(), bounds.end());
+		let position = physical.transform().position;
+		let velocity = physical.getVelocity(1.0) * physical.elasticity();
+		
+		if start.x < -halfSize.x {
+			let x = position.x - halfSize.x - start.x;
+			physical.transformMut().position.x = x;
+			physical.lastTransformMut().position.x = x + velocity.x;
+		} else if end.x > halfSize.x {
+			let x = position.x + halfSize.x - end.x;
+			physical.transformMut().position.x = x;
+			physical.lastTransformMut().position.x = x + velocity.x;
+		}
+		
+		if start.y < -halfSize.y {
+			let y = position.y - halfSize.y - start.y;
+			physical.transformMut().position.y = y;
+			physical.lastTransformMut().position.y = y + velocity.y;
+		} else if end.y > halfSize.y {
+			let y = position.y + halfSize.y - end.y;
+			physical.transformMut().position.y = y;
+			physical.lastTransformMut().position.y = y + velocity.y;
+		}
+	}
+	
+	fn collideCurved(&self, physical: &mut dyn Physical) {
+		let penetration = {
+			let shape = physical.shape();
+			let transform = physical.transform();
+			if shape.isCircle() {
+				self.distance(transform.position).map(|(distance, normal)| (distance + shape.radius(transform), normal))
+			} else {
+				// Deepest vertex decides for polygon shapes
+				shape.vertices(transform).into_iter()
+					.filter_map(|vertex| self.distance(vertex))
+					.max_by(|a, b| a.0.total_cmp(&b.0))
+			}
+		};
+		let Some((depth, normal)) = penetration else {
+			return;
+		};
+		if depth > 0.0 {
+			physical.pushOut(-normal, depth);
+		}
+	}
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::{Quat, Vec3};
+use crate::simulation::ball::Ball;
+use crate::simulation::{solver, Physical};
+use crate::types::PhysicalRef;
+
+const DEFAULT_SEED: u32 = 1;
+
+/// Spawns balls at a steady rate, up to `maxCount` of its own alive at once
+#[derive(Debug)]
+pub struct Emitter {
+	id: usize,
+	pub enabled: bool,
+	pub position: Vec3,
+	pub direction: Vec3,
+	/// Largest angle either side of `direction` in radians
+	pub spread: f32,
+	pub speed: f32,
+	/// Balls per second
+	pub rate: f32,
+	pub minRadius: f32,
+	pub maxRadius: f32,
+	/// Colours run from start to end over every `maxCount` spawns
+	pub colorStart: Vec3,
+	pub colorEnd: Vec3,
+	pub maxCount: usize,
+	/// Seconds each ball lives for, zero to keep them
+	pub lifetime: f32,
+	/// Spawn fluid particles instead of balls, see `Physical::fluid`
+	pub fluid: bool,
+	/// Ids spawned by this emitter that are still in the solver
+	alive: Vec<usize>,
+	spawnedTotal: usize,
+	/// Fraction of a ball carried over to the next update
+	pending: f32,
+	/// Random spread and radii repeat for the same seed
+	seed: u32,
+	/// Xorshift state, reset from `seed`
+	state: u32,
+}
+
+impl Emitter {
+	pub fn new(position: Vec3, direction: Vec3) -> Self {
+		Self::withSeed(position, direction, DEFAULT_SEED)
+	}
+	
+	pub fn withSeed(position: Vec3, direction: Vec3, seed: u32) -> Self {
+		Self {
+			id: solver::newId(),
+			enabled: true,
+			position,
+			direction,
+			spread: 0.2,
+			speed: 400.0,
+			rate: 30.0,
+			minRadius: 4.0,
+			maxRadius: 6.0,
+			colorStart: Vec3::new(0.2, 0.5, 1.0),
+			colorEnd: Vec3::new(0.9, 0.9, 1.0),
+			maxCount: 500,
+			lifetime: 0.0,
+			fluid: false,
+			alive: Vec::new(),
+			spawnedTotal: 0,
+			pending: 0.0,
+			seed,
+			state: Self::initialState(seed),
+		}
+	}
+	
+	/// Xorshift never leaves zero, so it's kept odd
+	fn initialState(seed: u32) -> u32 {
+		seed.wrapping_mul(0x9E37_79B9) | 1
+	}
+	
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	
+	pub fn alive(&self) -> &[usize] {
+		&self.alive
+	}
+	
+	pub fn spawnedTotal(&self) -> usize {
+		self.spawnedTotal
+	}
+	
+	pub fn seed(&self) -> u32 {
+		self.seed
+	}
+	
+	/// Restart the random sequence from `seed`
+	pub fn setSeed(&mut self, seed: u32) {
+		self.seed = seed;
+		self.state = Self::initialState(seed);
+	}
+	
+	/// Uniform in [0, 1)
+	fn random(&mut self) -> f32 {
+		self.state ^= self.state << 13;
+		self.state ^= self.state >> 17;
+		self.state ^= self.state << 5;
+		(self.state >> 8) as f32 / (1 << 24) as f32
+	}
+	
+	/// Forget balls that left the solver, then spawn what the rate allows this update.
+	/// `dt` is the integration step the spawn velocity is given for, `occupied` tells whether a
+	/// circle at a position overlaps something already in the solver. A blocked spawn ends the
+	/// burst and is retried next update, without piling up a backlog while the emitter stays covered
+	pub fn emit<F: Fn(Vec3, f32) -> bool>(&mut self, physicals: &BTreeMap<usize, PhysicalRef>, time: f32, dt: f32, occupied: F) -> Vec<Ball> {
+		self.alive.retain(|id| physicals.contains_key(id));
+		if !self.enabled || self.rate <= 0.0 {
+			self.pending = 0.0;
+			return Vec::new();
+		}
+		
+		self.pending += self.rate * time;
+		let count = (self.pending as usize).min(self.maxCount.saturating_sub(self.alive.len()));
+		self.pending = self.pending.fract();
+		
+		let direction = self.direction.normalize_or(Vec3::Y);
+		let (minRadius, maxRadius) = (self.minRadius.min(self.maxRadius), self.maxRadius.max(self.minRadius));
+		let mut balls = Vec::with_capacity(count);
+		for i in 0..count {
+			let angle = (self.random() * 2.0 - 1.0) * self.spread;
+			let radius = minRadius + (maxRadius - minRadius) * self.random();
+			let t = (self.spawnedTotal % self.maxCount.max(1)) as f32 / self.maxCount.max(1) as f32;
+			let velocity = Quat::from_rotation_z(angle) * direction * self.speed;
+			
+			// Spread a burst along its path as if spawned evenly over the update
+			let position = self.position + velocity * time * i as f32 / count as f32;
+			let blocked = occupied(position, radius) || balls.iter().any(|ball: &Ball| {
+				let other = ball.transform().position.distance(position);
+				other < ball.shape().radius(ball.transform()) + radius
+			});
+			if blocked {
+				self.pending = 1.0;
+				break;
+			}
+			
+			let mut ball = Ball::new(position, Vec3::splat(radius * 2.0));
+			ball.color = self.colorStart.lerp(self.colorEnd, t);
+			ball.setVelocity(velocity, dt);
+			ball.setFluid(self.fluid);
+			if self.lifetime > 0.0 {
+				ball.setLifetime(Some(self.lifetime));
+			}
+			self.alive.push(ball.id());
+			self.spawnedTotal += 1;
+			balls.push(ball);
+		}
+		balls
+	}
+	
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui) {
+		ui.text(format!("Alive: {}/{}", self.alive.len(), self.maxCount));
+		ui.text(format!("Spawned: {}", self.spawnedTotal));
+		ui.checkbox("Enabled", &mut self.enabled);
+		ui.checkbox("Fluid", &mut self.fluid);
+		ui.input_float3("Position", self.position.as_mut()).build();
+		ui.input_float3("Direction", self.direction.as_mut()).build();
+		let mut spread = self.spread.to_degrees();
+		if ui.slider_f32("Spread", &mut spread, 0.0, 180.0) {
+			self.spread = spread.to_radians();
+		}
+		ui.input_float("Speed", &mut self.speed);
+		ui.input_float("Rate", &mut self.rate);
+		ui.input_float("Min radius", &mut self.minRadius);
+		ui.input_float("Max radius", &mut self.maxRadius);
+		ui.input_float("Lifetime", &mut self.lifetime);
+		ui.color_edit3("Color start", self.colorStart.as_mut());
+		ui.color_edit3("Color end", self.colorEnd.as_mut());
+		let mut maxCount = self.maxCount as i32;
+		if ui.input_int("Max count", &mut maxCount) {
+			self.maxCount = maxCount.max(0) as usize;
+		}
+		let mut seed = self.seed as i32;
+		if ui.input_int("Seed", &mut seed) {
+			self.setSeed(seed as u32);
+		}
+		self.rate = self.rate.max(0.0);
+		self.lifetime = self.lifetime.max(0.0);
+		self.minRadius = self.minRadius.max(0.5);
+		self.maxRadius = self.maxRadius.max(0.5);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use glam::Vec3;
+	use crate::simulation::Physical;
+	use super::Emitter;
+	
+	#[test]
+	fn emittersWithTheSameSeedSpawnTheSame() {
+		let spawn = |seed| {
+			let mut emitter = Emitter::withSeed(Vec3::ZERO, Vec3::Y, seed);
+			emitter.spread = 1.0;
+			let balls = emitter.emit(&BTreeMap::new(), 1.0, 1.0 / 60.0, |_, _| false);
+			balls.iter().map(|ball| (ball.transform().scale, ball.getVelocity(1.0 / 60.0))).collect::<Vec<_>>()
+		};
+		let first = spawn(7);
+		// Ids handed out in between must not change the sequence
+		Emitter::new(Vec3::ZERO, Vec3::Y);
+		assert_eq!(first, spawn(7));
+		assert_ne!(first, spawn(8));
+	}
+}
@@ -0,0 +1,78 @@
+use std::f32::consts::PI;
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::Vec3;
+
+/// Density, pressure and viscosity pass shared by every fluid particle in a solver.
+/// Fluid particles don't collide with each other, pressure is what keeps them apart
+#[derive(Copy, Clone, Debug)]
+pub struct FluidSettings {
+	/// Neighbours further than this don't affect a particle
+	pub smoothingRadius: f32,
+	/// Density the fluid settles at, anything denser pushes apart
+	pub restDensity: f32,
+	/// Pressure per unit of density above rest
+	pub stiffness: f32,
+	/// How strongly neighbours even out their velocities
+	pub viscosity: f32,
+}
+
+impl Default for FluidSettings {
+	fn default() -> Self {
+		Self {
+			smoothingRadius: 16.0,
+			restDensity: 0.02,
+			stiffness: 1.0e6,
+			viscosity: 20.0,
+		}
+	}
+}
+
+impl FluidSettings {
+	/// Poly6 kernel in 2d, weighs a neighbour's mass into the density
+	pub fn density(&self, distance: f32) -> f32 {
+		let h = self.smoothingRadius;
+		if distance >= h {
+			return 0.0;
+		}
+		let x = h * h - distance * distance;
+		4.0 / (PI * h.powi(8)) * x * x * x
+	}
+	
+	/// Spiky kernel gradient in 2d for `offset` from a neighbour, points away from it
+	pub fn pressureGradient(&self, offset: Vec3) -> Vec3 {
+		let h = self.smoothingRadius;
+		let distance = offset.length();
+		if distance >= h || distance <= 0.0 {
+			return Vec3::ZERO;
+		}
+		let x = h - distance;
+		offset / distance * (30.0 / (PI * h.powi(5)) * x * x)
+	}
+	
+	/// Viscosity kernel laplacian in 2d
+	pub fn viscosityLaplacian(&self, distance: f32) -> f32 {
+		let h = self.smoothingRadius;
+		if distance >= h {
+			return 0.0;
+		}
+		40.0 / (PI * h.powi(5)) * (h - distance)
+	}
+	
+	/// Negative pressure would pull particles into clumps, so it stops at zero
+	pub fn pressure(&self, density: f32) -> f32 {
+		(self.stiffness * (density - self.restDensity)).max(0.0)
+	}
+	
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui) {
+		ui.input_float("Smoothing radius", &mut self.smoothingRadius);
+		ui.input_float("Rest density", &mut self.restDensity);
+		ui.input_float("Stiffness", &mut self.stiffness);
+		ui.input_float("Viscosity", &mut self.viscosity);
+		self.smoothingRadius = self.smoothingRadius.max(1.0);
+		self.restDensity = self.restDensity.max(0.0);
+		self.stiffness = self.stiffness.max(0.0);
+		self.viscosity = self.viscosity.max(0.0);
+	}
+}
@@ -0,0 +1,237 @@
+use std::any::Any;
+use std::fmt::Debug;
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::Vec3;
+use crate::simulation::region::AABB;
+use crate::simulation::solver;
+use crate::simulation::Physical;
+
+pub trait ForceField: Any + Debug + Send + Sync {
+	fn id(&self) -> usize;
+	
+	fn name(&self) -> &str;
+	
+	/// Accelerate or push a physical, called once per sub step before it's integrated
+	fn apply(&self, physical: &mut dyn Physical, dt: f32);
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui);
+}
+
+/// Point gravity pulling towards a position, negative strength pushes away
+#[derive(Debug)]
+pub struct Attractor {
+	id: usize,
+	pub position: Vec3,
+	pub strength: f32,
+	/// Distances below this are clamped so the pull stays finite near the center
+	pub minDistance: f32,
+}
+
+impl Attractor {
+	pub fn new(position: Vec3, strength: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			position,
+			strength,
+			minDistance: 10.0,
+		}
+	}
+}
+
+impl ForceField for Attractor {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Attractor"
+	}
+	
+	fn apply(&self, physical: &mut dyn Physical, _dt: f32) {
+		let offset = self.position - physical.transform().position;
+		let distanceSq = offset.length_squared().max(self.minDistance * self.minDistance);
+		physical.accelerate(offset.normalize_or_zero() * (self.strength / distanceSq));
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float3("Position", self.position.as_mut()).build();
+		ui.input_float("Strength", &mut self.strength);
+		ui.input_float("Min distance", &mut self.minDistance);
+		self.minDistance = self.minDistance.max(0.0);
+	}
+}
+
+/// Swirl around a position that fades out towards the radius, with an optional pull inwards
+#[derive(Debug)]
+pub struct Vortex {
+	id: usize,
+	pub position: Vec3,
+	pub radius: f32,
+	/// Tangential acceleration at the center, positive spins counter-clockwise
+	pub strength: f32,
+	/// Acceleration towards the center at the center
+	pub pull: f32,
+}
+
+impl Vortex {
+	pub fn new(position: Vec3, radius: f32, strength: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			position,
+			radius,
+			strength,
+			pull: 0.0,
+		}
+	}
+}
+
+impl ForceField for Vortex {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Vortex"
+	}
+	
+	fn apply(&self, physical: &mut dyn Physical, _dt: f32) {
+		let offset = physical.transform().position - self.position;
+		let distance = offset.length();
+		if distance >= self.radius || distance <= f32::EPSILON {
+			return;
+		}
+		
+		let falloff = 1.0 - distance / self.radius;
+		let normal = offset / distance;
+		let tangent = Vec3::new(-normal.y, normal.x, 0.0);
+		physical.accelerate((tangent * self.strength - normal * self.pull) * falloff);
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float3("Position", self.position.as_mut()).build();
+		ui.input_float("Radius", &mut self.radius);
+		ui.input_float("Strength", &mut self.strength);
+		ui.input_float("Pull", &mut self.pull);
+		self.radius = self.radius.max(0.0);
+	}
+}
+
+/// Uniform force, lighter physicals are blown further
+#[derive(Debug)]
+pub struct Wind {
+	id: usize,
+	pub force: Vec3,
+}
+
+impl Wind {
+	pub fn new(force: Vec3) -> Self {
+		Self {
+			id: solver::newId(),
+			force,
+		}
+	}
+}
+
+impl ForceField for Wind {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Wind"
+	}
+	
+	fn apply(&self, physical: &mut dyn Physical, _dt: f32) {
+		physical.applyForce(self.force);
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float3("Force", self.force.as_mut()).build();
+	}
+}
+
+/// Linear drag opposing velocity
+#[derive(Debug)]
+pub struct Drag {
+	id: usize,
+	pub coefficient: f32,
+}
+
+impl Drag {
+	pub fn new(coefficient: f32) -> Self {
+		Self {
+			id: solver::newId(),
+			coefficient,
+		}
+	}
+}
+
+impl ForceField for Drag {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Drag"
+	}
+	
+	fn apply(&self, physical: &mut dyn Physical, dt: f32) {
+		let velocity = physical.getVelocity(dt);
+		physical.applyForce(-velocity * self.coefficient);
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float("Coefficient", &mut self.coefficient);
+		self.coefficient = self.coefficient.max(0.0);
+	}
+}
+
+/// Limits another field to physicals positioned inside an area
+#[derive(Debug)]
+pub struct RegionField {
+	id: usize,
+	pub area: AABB,
+	pub field: Box<dyn ForceField>,
+}
+
+impl RegionField {
+	pub fn new<F: ForceField>(area: AABB, field: F) -> Self {
+		Self {
+			id: solver::newId(),
+			area,
+			field: Box::new(field),
+		}
+	}
+}
+
+impl ForceField for RegionField {
+	fn id(&self) -> usize {
+		self.id
+	}
+	
+	fn name(&self) -> &str {
+		"Region"
+	}
+	
+	fn apply(&self, physical: &mut dyn Physical, dt: f32) {
+		if self.area.containsPoint(physical.transform().position) {
+			self.field.apply(physical, dt);
+		}
+	}
+	
+	#[cfg(feature = "graphics")]
+	fn gui(&mut self, ui: &Ui) {
+		ui.input_float3("Area position", self.area.position.as_mut()).build();
+		ui.input_float3("Area size", self.area.size.as_mut()).build();
+		self.area.size = self.area.size.max(Vec3::ZERO);
+		if let Some(_node) = ui.tree_node(self.field.name().to_string()) {
+			self.field.gui(ui);
+		}
+	}
+}
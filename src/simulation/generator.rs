@@ -0,0 +1,59 @@
+use glam::Vec3;
+
+#[derive(Clone, Debug)]
+pub struct RopeOptions {
+	/// Links between the two ends, one less than the particles
+	pub segments: usize,
+	pub particleRadius: f32,
+	/// Link stiffness, 1 for a rigid chain
+	pub stiffness: f32,
+	pub pinStart: bool,
+	pub pinEnd: bool,
+	pub color: Vec3,
+}
+
+impl Default for RopeOptions {
+	fn default() -> Self {
+		Self {
+			segments: 20,
+			particleRadius: 4.0,
+			stiffness: 1.0,
+			pinStart: true,
+			pinEnd: false,
+			color: Vec3::new(0.8, 0.6, 0.3),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct ClothOptions {
+	/// Particles along x
+	pub columns: usize,
+	/// Particles along y
+	pub rows: usize,
+	pub particleRadius: f32,
+	/// Links to the direct neighbours
+	pub stiffness: f32,
+	/// Diagonal links, resisting shearing
+	pub shearStiffness: f32,
+	/// Links skipping a particle, resisting folding
+	pub bendStiffness: f32,
+	/// Pin the top row in place
+	pub pinTop: bool,
+	pub color: Vec3,
+}
+
+impl Default for ClothOptions {
+	fn default() -> Self {
+		Self {
+			columns: 16,
+			rows: 12,
+			particleRadius: 3.0,
+			stiffness: 1.0,
+			shearStiffness: 0.5,
+			bendStiffness: 0.2,
+			pinTop: true,
+			color: Vec3::new(0.3, 0.6, 0.9),
+		}
+	}
+}
@@ -2,9 +2,33 @@ mod transform;
 pub mod camera;
 pub mod ball;
 mod solver;
+#[cfg(feature = "graphics")]
+mod ball_renderable;
+#[cfg(feature = "graphics")]
+mod polygon_renderable;
+#[cfg(feature = "graphics")]
+mod solver_renderable;
 pub mod region;
+pub mod constraint;
+pub mod constraint_box;
+pub mod contact;
+pub mod emitter;
+pub mod container;
+pub mod fluid;
+pub mod force_field;
+pub mod generator;
+pub mod obstacle;
+pub mod sensor;
+pub mod shape;
 
 pub use transform::Transform;
 
 pub use solver::Physical;
 pub use solver::Solver;
+pub use solver::RayHit;
+#[cfg(feature = "graphics")]
+pub use ball_renderable::BallRenderable;
+#[cfg(feature = "graphics")]
+pub use polygon_renderable::PolygonRenderable;
+#[cfg(feature = "graphics")]
+pub use solver_renderable::SolverRenderable;
@@ -0,0 +1,100 @@
+use glam::Vec3;
+use crate::simulation::region::AABB;
+use crate::simulation::shape::{castPolygon, circlePolygon, isConvex, polygonBounds, polygonPolygon, signedArea};
+use crate::simulation::solver;
+use crate::simulation::Physical;
+
+/// Immovable level geometry in world space, a line segment or a convex polygon
+#[derive(Debug)]
+pub struct Obstacle {
+	id: usize,
+	/// Two points for a segment, otherwise counter-clockwise
+	vertices: Vec<Vec3>,
+	bounds: AABB,
+	pub color: Vec3,
+}
+
+impl Obstacle {
+	pub fn segment(start: Vec3, end: Vec3) -> Self {
+		Self::new(vec![start, end])
+	}
+	
+	/// Convex polygon in any winding order, `None` for fewer than three vertices or a concave outline
+	pub fn polygon(mut vertices: Vec<Vec3>) -> Option<Self> {
+		if !isConvex(&vertices) {
+			return None;
+		}
+		if signedArea(&vertices) < 0.0 {
+			vertices.reverse();
+		}
+		Some(Self::new(vertices))
+	}
+	
+	fn new(vertices: Vec<Vec3>) -> Self {
+		Self {
+			id: solver::newId(),
+			bounds: polygonBounds(&vertices),
+			vertices,
+			color: Vec3::splat(0.6),
+		}
+	}
+	
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	
+	pub fn isSegment(&self) -> bool {
+		self.vertices.len() == 2
+	}
+	
+	pub fn vertices(&self) -> &[Vec3] {
+		&self.vertices
+	}
+	
+	pub fn bounds(&self) -> &AABB {
+		&self.bounds
+	}
+	
+	/// Sweep a circle of `radius` along a unit `direction`, returns the distance travelled and the surface normal
+	pub fn cast(&self, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32) -> Option<(f32, Vec3)> {
+		castPolygon(origin, direction, &self.vertices, radius, maxDistance)
+	}
+	
+	/// Push a physical out, bouncing it off by its elasticity
+	pub fn collide(&self, physical: &mut dyn Physical) {
+		if physical.inverseMass() <= 0.0 {
+			return;
+		}
+		let contact = {
+			let shape = physical.shape();
+			let transform = physical.transform();
+			if shape.isCircle() {
+				circlePolygon(transform.position, shape.radius(transform), &self.vertices)
+			} else {
+				polygonPolygon(&shape.vertices(transform), &self.vertices)
+			}
+		};
+		if let Some(contact) = contact {
+			physical.pushOut(contact.normal, contact.depth);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use glam::Vec3;
+	use super::Obstacle;
+	
+	#[test]
+	fn polygonsMustBeConvex() {
+		assert!(Obstacle::polygon(Vec::new()).is_none());
+		assert!(Obstacle::polygon(vec![Vec3::ZERO, Vec3::X]).is_none());
+		assert!(Obstacle::polygon(vec![Vec3::ZERO, Vec3::X, Vec3::X * 2.0]).is_none());
+		// Arrow head, the middle point turns the other way
+		assert!(Obstacle::polygon(vec![Vec3::ZERO, Vec3::new(2.0, 1.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 1.0, 0.0)]).is_none());
+		
+		// Clockwise is fine, it's stored counter-clockwise
+		let square = Obstacle::polygon(vec![Vec3::ZERO, Vec3::Y, Vec3::new(1.0, 1.0, 0.0), Vec3::X]).unwrap();
+		assert_eq!(square.vertices()[1], Vec3::new(1.0, 1.0, 0.0));
+	}
+}
@@ -0,0 +1,119 @@
+use glam::Mat4;
+use crate::graphics::{LineRenderer, Renderable};
+use crate::graphics::mesh::{Mesh, Vertex};
+use crate::types::{newMeshRef, GlRef, MeshRef, ShaderRef, SolverRef};
+
+/// Renderable for box and polygon shapes, polygon obstacles and constraint boxes, rebuilt every frame
+pub struct PolygonRenderable {
+	mesh: MeshRef,
+	shader: ShaderRef,
+	verletSolver: SolverRef,
+}
+
+impl PolygonRenderable {
+	pub fn new(gl: GlRef, shader: ShaderRef, verletSolver: SolverRef) -> Self {
+		let mesh = Mesh::simple(gl, Vec::new(), Some(Vec::new()));
+		Self {
+			mesh: newMeshRef(mesh),
+			shader,
+			verletSolver,
+		}
+	}
+	
+	fn data(&self) -> (Vec<Vertex>, Vec<u32>) {
+		let mut vertices = Vec::new();
+		let mut indices = Vec::new();
+		
+		for (_, physical) in self.verletSolver.borrow().getPhysicals().iter() {
+			let physical = physical.read().unwrap();
+			let polygon = physical.shape().vertices(physical.transform());
+			if polygon.len() < 3 {
+				continue;
+			}
+			
+			// Triangle fan, shapes are convex
+			let start = vertices.len() as u32;
+			for position in polygon.iter() {
+				vertices.push(Vertex {
+					position: *position,
+					color: physical.color(),
+				});
+			}
+			for i in 1..polygon.len() as u32 - 1 {
+				indices.push(start);
+				indices.push(start + i);
+				indices.push(start + i + 1);
+			}
+		}
+		
+		let solver = self.verletSolver.borrow();
+		for obstacle in solver.getObstacles().iter() {
+			if obstacle.vertices().len() < 3 {
+				continue;
+			}
+			let start = vertices.len() as u32;
+			for position in obstacle.vertices().iter() {
+				vertices.push(Vertex {
+					position: *position,
+					color: obstacle.color,
+				});
+			}
+			for i in 1..obstacle.vertices().len() as u32 - 1 {
+				indices.push(start);
+				indices.push(start + i);
+				indices.push(start + i + 1);
+			}
+		}
+		
+		for constraintBox in solver.getBoxes().iter() {
+			let Some(polygon) = constraintBox.polygon(solver.getPhysicals()) else {
+				continue;
+			};
+			let start = vertices.len() as u32;
+			for position in polygon.iter() {
+				vertices.push(Vertex {
+					position: *position,
+					color: constraintBox.color,
+				});
+			}
+			indices.extend_from_slice(&[start, start + 1, start + 2, start + 2, start + 3, start]);
+		}
+		
+		(vertices, indices)
+	}
+}
+
+impl Renderable for PolygonRenderable {
+	fn meshRef(&self) -> Option<&MeshRef> {
+		Option::from(&self.mesh)
+	}
+	
+	fn shaderRef(&self) -> Option<&ShaderRef> {
+		Option::from(&self.shader)
+	}
+	
+	fn render(&self, projViewMat: &Mat4, _dt: f32, _lineRenderer: &mut LineRenderer) -> Result<(), String> {
+		if let Some(mesh) = self.meshRef() && let Some(shader) = self.shaderRef() {
+			let mut mesh = mesh.borrow_mut();
+			let shader = shader.read().unwrap();
+			
+			let (vertices, indices) = self.data();
+			if indices.is_empty() {
+				return Ok(());
+			}
+			mesh.updateData(vertices, Some(indices))?;
+			
+			shader.bind();
+			shader.setMatrix4f("u_pvm", projViewMat);
+			
+			mesh.draw();
+		}
+		Ok(())
+	}
+}
+
+impl Drop for PolygonRenderable {
+	fn drop(&mut self) {
+		self.destroy();
+	}
+}
@@ -50,6 +50,35 @@ impl AABB {
 			&& start1.z <= end2.z && end1.z >= start2.z
 	}
 	
+	/// Slab test, true if any part of the segment `start -> end` is inside
+	pub fn intersectsSegment(&self, start: Vec3, end: Vec3) -> bool {
+		let min = self.start();
+		let max = self.end();
+		let dir = end - start;
+		
+		let mut tMin = 0.0f32;
+		let mut tMax = 1.0f32;
+		for axis in 0..3 {
+			if dir[axis].abs() <= f32::EPSILON {
+				if start[axis] < min[axis] || start[axis] > max[axis] {
+					return false;
+				}
+				continue;
+			}
+			let mut t1 = (min[axis] - start[axis]) / dir[axis];
+			let mut t2 = (max[axis] - start[axis]) / dir[axis];
+			if t1 > t2 {
+				std::mem::swap(&mut t1, &mut t2);
+			}
+			tMin = tMin.max(t1);
+			tMax = tMax.min(t2);
+			if tMin > tMax {
+				return false;
+			}
+		}
+		true
+	}
+	
 	pub fn containsPoint(&self, point: Vec3) -> bool {
 		let start = self.start();
 		let end = self.end();
@@ -1,10 +1,17 @@
 use std::fmt::Debug;
-use glam::{Mat4, Vec3};
+use glam::Vec3;
+#[cfg(feature = "graphics")]
+use glam::Mat4;
 use tracing::warn;
+#[cfg(feature = "graphics")]
 use crate::graphics::{LineRenderer, Renderable};
 use crate::simulation::region::AABB;
+#[cfg(feature = "graphics")]
 use crate::types::{MeshRef, ShaderRef};
 
+/// Nodes don't split below this size, values piled on one spot would otherwise split forever
+const MIN_NODE_SIZE: f32 = 1.0;
+
 #[derive(Copy, Clone, Debug)]
 pub enum Orientation {
 	Vertical,
@@ -54,6 +61,17 @@ impl<T> BSPGrid<T> {
 		self.right = None;
 	}
 	
+	/// Drop values in place, the tree keeps its splits until the next clear
+	pub fn retain<F: Fn(&T) -> bool>(&mut self, keep: &F) {
+		self.values.retain(|value| keep(value));
+		if let Some(ref mut leaf) = self.left {
+			leaf.retain(keep);
+		}
+		if let Some(ref mut leaf) = self.right {
+			leaf.retain(keep);
+		}
+	}
+	
 	pub fn depth(&self) -> usize {
 		let left = match self.left {
 			None => 0,
@@ -69,6 +87,14 @@ impl<T> BSPGrid<T> {
 	pub fn bounds(&self) -> &AABB {
 		&self.bounds
 	}
+	
+	/// Halving along the orientation would go under `MIN_NODE_SIZE`
+	fn atMinSize(&self) -> bool {
+		match self.orientation {
+			Orientation::Vertical => self.bounds.size.y / 2.0 < MIN_NODE_SIZE,
+			Orientation::Horizontal => self.bounds.size.x / 2.0 < MIN_NODE_SIZE,
+		}
+	}
 }
 
 impl<T: Clone + Debug> BSPGrid<T> {
@@ -111,7 +137,7 @@ impl<T: Clone + Debug> BSPGrid<T> {
 			return false;
 		}
 		
-		if self.values.len() < self.capacity && self.left.is_none() {
+		if self.left.is_none() && (self.values.len() < self.capacity || self.atMinSize()) {
 			self.values.push(value);
 			return true;
 		}
@@ -145,8 +171,26 @@ impl<T: Clone + Debug> BSPGrid<T> {
 		found.append(&mut self.right.as_ref().unwrap().findInArea(area, overlaps));
 		found
 	}
+	
+	/// Values of every node the segment passes through, node bounds grown by `padding` on each side
+	pub fn findOnSegment(&self, start: Vec3, end: Vec3, padding: f32) -> Vec<T> {
+		let mut found = Vec::new();
+		if !self.bounds.grow(Vec3::splat(padding * 2.0)).intersectsSegment(start, end) {
+			return found;
+		}
+		
+		found.extend(self.values.iter().cloned());
+		if self.left.is_none() {
+			return found;
+		}
+		
+		found.append(&mut self.left.as_ref().unwrap().findOnSegment(start, end, padding));
+		found.append(&mut self.right.as_ref().unwrap().findOnSegment(start, end, padding));
+		found
+	}
 }
 
+#[cfg(feature = "graphics")]
 impl<T> Renderable for BSPGrid<T> {
 	fn meshRef(&self) -> Option<&MeshRef> {
 		None
@@ -176,3 +220,21 @@ impl<T> Renderable for BSPGrid<T> {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use glam::Vec3;
+	use crate::simulation::region::AABB;
+	use super::BSPGrid;
+	
+	#[test]
+	fn treeStopsSplittingValuesOnOneSpot() {
+		let mut tree = BSPGrid::new(4, AABB::centered(Vec3::ZERO, Vec3::splat(1000.0)));
+		for i in 0..1000 {
+			// Off the split lines, points on one land in both halves
+			assert!(tree.insert(i, &|_, bounds: &AABB| bounds.containsPoint(Vec3::splat(0.3))));
+		}
+		assert!(tree.depth() < 40, "tree is {} deep", tree.depth());
+		assert_eq!(tree.findInArea(&AABB::centered(Vec3::splat(0.3), Vec3::ONE), &|_, _| true).len(), 1000);
+	}
+}
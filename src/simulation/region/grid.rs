@@ -0,0 +1,86 @@
+use glam::Vec3;
+use crate::simulation::region::AABB;
+
+/// Fixed cells over an area, a value is stored in every cell it overlaps.
+/// Suits large, overlapping values a tree would keep splitting around
+#[derive(Clone)]
+pub struct UniformGrid<T> {
+	bounds: AABB,
+	cellSize: Vec3,
+	columns: usize,
+	rows: usize,
+	cells: Vec<Vec<T>>,
+}
+
+impl<T> UniformGrid<T> {
+	pub fn new(cells: usize, bounds: AABB) -> UniformGrid<T> {
+		let cells = cells.max(1);
+		let mut grid = Self {
+			bounds,
+			cellSize: (bounds.size / cells as f32).max(Vec3::splat(f32::EPSILON)),
+			columns: cells,
+			rows: cells,
+			cells: Vec::new(),
+		};
+		grid.cells.resize_with(cells * cells, Vec::new);
+		grid
+	}
+	
+	pub fn clear(&mut self) {
+		for cell in self.cells.iter_mut() {
+			cell.clear();
+		}
+	}
+	
+	pub fn bounds(&self) -> &AABB {
+		&self.bounds
+	}
+	
+	/// Inclusive cell range covering an area, `None` outside the grid
+	fn range(&self, area: &AABB) -> Option<(usize, usize, usize, usize)> {
+		if !self.bounds.overlaps(area) {
+			return None;
+		}
+		let start = ((area.start() - self.bounds.start()) / self.cellSize).max(Vec3::ZERO);
+		let end = ((area.end() - self.bounds.start()) / self.cellSize).max(Vec3::ZERO);
+		Some((
+			(start.x as usize).min(self.columns - 1),
+			(start.y as usize).min(self.rows - 1),
+			(end.x as usize).min(self.columns - 1),
+			(end.y as usize).min(self.rows - 1),
+		))
+	}
+}
+
+impl<T: Clone> UniformGrid<T> {
+	/// False if `bounds` is outside the grid
+	pub fn insert(&mut self, value: T, bounds: &AABB) -> bool {
+		let Some((x1, y1, x2, y2)) = self.range(bounds) else {
+			return false;
+		};
+		for y in y1..=y2 {
+			for x in x1..=x2 {
+				self.cells[x + y * self.columns].push(value.clone());
+			}
+		}
+		true
+	}
+	
+	/// Values in the cells touched by the area, a value spanning several of them is returned once for each
+	pub fn findInArea<F: Fn(&T, &AABB) -> bool>(&self, area: &AABB, overlaps: &F) -> Vec<T> {
+		let mut found = Vec::new();
+		let Some((x1, y1, x2, y2)) = self.range(area) else {
+			return found;
+		};
+		for y in y1..=y2 {
+			for x in x1..=x2 {
+				for value in self.cells[x + y * self.columns].iter() {
+					if overlaps(value, area) {
+						found.push(value.clone());
+					}
+				}
+			}
+		}
+		found
+	}
+}
@@ -1,8 +1,10 @@
 mod bsp;
 mod aabb;
 mod quadtree;
+mod grid;
 
 pub use aabb::AABB;
 #[allow(unused)]
 pub use quadtree::QuadTree;
 pub use bsp::BSPGrid;
+pub use grid::UniformGrid;
@@ -1,10 +1,14 @@
 #![allow(unused)]
 
 use std::fmt::Debug;
-use glam::{Mat4, Vec3};
+use glam::Vec3;
+#[cfg(feature = "graphics")]
+use glam::Mat4;
 use tracing::warn;
+#[cfg(feature = "graphics")]
 use crate::graphics::{LineRenderer, Renderable};
 use crate::simulation::region::AABB;
+#[cfg(feature = "graphics")]
 use crate::types::{MeshRef, ShaderRef};
 
 #[derive(Clone)]
@@ -136,6 +140,7 @@ impl<T: Clone + Debug> QuadTree<T> {
     }
 }
 
+#[cfg(feature = "graphics")]
 impl<T> Renderable for QuadTree<T> {
     fn meshRef(&self) -> Option<&MeshRef> {
         None
@@ -0,0 +1,219 @@
+use std::f32::consts::TAU;
+#[cfg(feature = "graphics")]
+use dear_imgui_rs::Ui;
+use glam::{vec3, Vec3};
+use crate::simulation::region::AABB;
+use crate::simulation::shape::{polygonBounds, Shape};
+use crate::simulation::{solver, Transform};
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[derive(Clone, Debug)]
+pub enum SensorArea {
+	Aabb(AABB),
+	Circle {
+		center: Vec3,
+		radius: f32,
+	},
+	/// Convex polygon in world space
+	Polygon(Vec<Vec3>),
+}
+
+impl SensorArea {
+	pub fn name(&self) -> &str {
+		match self {
+			SensorArea::Aabb(_) => "Aabb",
+			SensorArea::Circle { .. } => "Circle",
+			SensorArea::Polygon(_) => "Polygon",
+		}
+	}
+	
+	pub fn bounds(&self) -> AABB {
+		match self {
+			SensorArea::Aabb(area) => *area,
+			SensorArea::Circle { center, radius } => AABB::centered(*center, Vec3::splat(radius * 2.0)),
+			SensorArea::Polygon(vertices) => polygonBounds(vertices),
+		}
+	}
+	
+	/// Shape and transform to run the narrow phase against
+	pub fn shape(&self) -> (Shape, Transform) {
+		match self {
+			SensorArea::Aabb(area) => (Shape::Box, Transform {
+				position: area.center(),
+				scale: area.size,
+				..Default::default()
+			}),
+			SensorArea::Circle { center, radius } => (Shape::Circle, Transform {
+				position: *center,
+				scale: Vec3::splat(radius * 2.0),
+				..Default::default()
+			}),
+			SensorArea::Polygon(vertices) => (Shape::Polygon(vertices.clone()), Transform::default()),
+		}
+	}
+	
+	pub fn outline(&self) -> Vec<Vec3> {
+		match self {
+			SensorArea::Aabb(area) => {
+				let (start, end) = (area.start(), area.end());
+				vec![start, vec3(end.x, start.y, 0.0), end, vec3(start.x, end.y, 0.0)]
+			},
+			SensorArea::Circle { center, radius } => (0..CIRCLE_SEGMENTS).map(|i| {
+				let angle = i as f32 * TAU / CIRCLE_SEGMENTS as f32;
+				*center + vec3(angle.cos(), angle.sin(), 0.0) * *radius
+			}).collect(),
+			SensorArea::Polygon(vertices) => vertices.clone(),
+		}
+	}
+	
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui) {
+		match self {
+			SensorArea::Aabb(area) => {
+				ui.input_float3("Area position", area.position.as_mut()).build();
+				ui.input_float3("Area size", area.size.as_mut()).build();
+				area.size = area.size.max(Vec3::ZERO);
+			},
+			SensorArea::Circle { center, radius } => {
+				ui.input_float3("Center", center.as_mut()).build();
+				ui.input_float("Radius", radius);
+				*radius = radius.max(0.0);
+			},
+			SensorArea::Polygon(vertices) => {
+				ui.text(format!("Vertices: {}", vertices.len()));
+			},
+		}
+	}
+}
+
+/// Area that reports physicals overlapping it without colliding with them
+#[derive(Debug)]
+pub struct Sensor {
+	id: usize,
+	pub area: SensorArea,
+	pub color: Vec3,
+	/// Sorted ids overlapping as of the last update
+	inside: Vec<usize>,
+	entered: Vec<usize>,
+	stayed: Vec<usize>,
+	left: Vec<usize>,
+	/// Entries since creation or the last reset
+	entries: usize,
+}
+
+impl Sensor {
+	pub fn new(area: SensorArea) -> Self {
+		Self {
+			id: solver::newId(),
+			area,
+			color: vec3(1.0, 0.8, 0.2),
+			inside: Vec::new(),
+			entered: Vec::new(),
+			stayed: Vec::new(),
+			left: Vec::new(),
+			entries: 0,
+		}
+	}
+	
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	
+	pub fn inside(&self) -> &[usize] {
+		&self.inside
+	}
+	
+	/// Started overlapping during the last update
+	pub fn entered(&self) -> &[usize] {
+		&self.entered
+	}
+	
+	/// Overlapping before and after the last update
+	pub fn stayed(&self) -> &[usize] {
+		&self.stayed
+	}
+	
+	/// Stopped overlapping or were removed during the last update
+	pub fn left(&self) -> &[usize] {
+		&self.left
+	}
+	
+	pub fn entries(&self) -> usize {
+		self.entries
+	}
+	
+	pub fn resetEntries(&mut self) {
+		self.entries = 0;
+	}
+	
+	/// Sort the new overlap into entered, stayed and left against the last one
+	pub(crate) fn update(&mut self, mut found: Vec<usize>) {
+		found.sort_unstable();
+		found.dedup();
+		self.entered = found.iter().filter(|id| self.inside.binary_search(id).is_err()).copied().collect();
+		self.stayed = found.iter().filter(|id| self.inside.binary_search(id).is_ok()).copied().collect();
+		self.left = self.inside.iter().filter(|id| found.binary_search(id).is_err()).copied().collect();
+		self.entries += self.entered.len();
+		self.inside = found;
+	}
+	
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui) {
+		ui.text(format!("Inside: {}", self.inside.len()));
+		ui.text(format!("Last update: {} entered, {} stayed, {} left", self.entered.len(), self.stayed.len(), self.left.len()));
+		ui.text(format!("Entries: {}", self.entries));
+		ui.same_line();
+		if ui.small_button("Reset") {
+			self.resetEntries();
+		}
+		self.area.gui(ui);
+	}
+}
+
+/// Area that removes every physical overlapping it
+#[derive(Debug)]
+pub struct KillZone {
+	id: usize,
+	pub area: SensorArea,
+	pub color: Vec3,
+	/// Physicals removed since creation or the last reset
+	killed: usize,
+}
+
+impl KillZone {
+	pub fn new(area: SensorArea) -> Self {
+		Self {
+			id: solver::newId(),
+			area,
+			color: vec3(1.0, 0.2, 0.2),
+			killed: 0,
+		}
+	}
+	
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	
+	pub fn killed(&self) -> usize {
+		self.killed
+	}
+	
+	pub fn resetKilled(&mut self) {
+		self.killed = 0;
+	}
+	
+	pub(crate) fn addKilled(&mut self, count: usize) {
+		self.killed += count;
+	}
+	
+	#[cfg(feature = "graphics")]
+	pub fn gui(&mut self, ui: &Ui) {
+		ui.text(format!("Killed: {}", self.killed));
+		ui.same_line();
+		if ui.small_button("Reset") {
+			self.resetKilled();
+		}
+		self.area.gui(ui);
+	}
+}
@@ -0,0 +1,387 @@
+use glam::{vec3, Vec3};
+use crate::simulation::region::AABB;
+use crate::simulation::Transform;
+
+/// Collision shape of a physical in unit size, scaled, rotated and moved by the physical's transform
+#[derive(Clone, Debug)]
+pub enum Shape {
+	/// Circle with a diameter of `scale.x`
+	Circle,
+	/// Axis aligned box the size of `scale`, ignores rotation
+	Box,
+	/// Box the size of `scale` rotated by the transform
+	OrientedBox,
+	/// Convex polygon from local vertices, scaled and rotated by the transform
+	Polygon(Vec<Vec3>),
+}
+
+/// Result of a narrow phase test, `normal` points from the second shape towards the first
+#[derive(Copy, Clone, Debug)]
+pub struct Contact {
+	pub point: Vec3,
+	pub normal: Vec3,
+	pub depth: f32,
+}
+
+const UNIT_BOX: [Vec3; 4] = [
+	vec3(-0.5, -0.5, 0.0),
+	vec3(0.5, -0.5, 0.0),
+	vec3(0.5, 0.5, 0.0),
+	vec3(-0.5, 0.5, 0.0),
+];
+
+impl Shape {
+	pub fn isCircle(&self) -> bool {
+		matches!(self, Shape::Circle)
+	}
+	
+	pub fn radius(&self, transform: &Transform) -> f32 {
+		transform.scale.x / 2.0
+	}
+	
+	/// World space vertices in counter-clockwise order, empty for circles
+	pub fn vertices(&self, transform: &Transform) -> Vec<Vec3> {
+		let mut vertices: Vec<Vec3> = match self {
+			Shape::Circle => return Vec::new(),
+			Shape::Box => UNIT_BOX.iter().map(|v| transform.position + *v * transform.scale).collect(),
+			Shape::OrientedBox => UNIT_BOX.iter().map(|v| transform.position + transform.rotation.mul_vec3(*v * transform.scale)).collect(),
+			Shape::Polygon(local) => local.iter().map(|v| transform.position + transform.rotation.mul_vec3(*v * transform.scale)).collect(),
+		};
+		if signedArea(&vertices) < 0.0 {
+			vertices.reverse();
+		}
+		vertices
+	}
+	
+	pub fn bounds(&self, transform: &Transform) -> AABB {
+		if self.isCircle() {
+			return AABB::centered(transform.position, transform.scale);
+		}
+		polygonBounds(&self.vertices(transform))
+	}
+	
+	pub fn containsPoint(&self, transform: &Transform, point: Vec3) -> bool {
+		if self.isCircle() {
+			return (point - transform.position).truncate().length() <= self.radius(transform);
+		}
+		polygonContainsPoint(&self.vertices(transform), point)
+	}
+	
+	/// Distance from the position to the furthest point of the shape, the same for any rotation
+	pub fn boundingRadius(&self, transform: &Transform) -> f32 {
+		if self.isCircle() {
+			return self.radius(transform);
+		}
+		self.vertices(transform).iter().map(|vertex| vertex.distance(transform.position)).fold(0.0, f32::max)
+	}
+	
+	/// Sweep a circle of `radius` from `origin` along a unit `direction`, zero radius for a ray.
+	/// Returns the distance travelled and the surface normal. A shape overlapping the origin is hit at zero only when moving deeper into it
+	pub fn cast(&self, transform: &Transform, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32) -> Option<(f32, Vec3)> {
+		if self.isCircle() {
+			return castCircle(origin, direction, transform.position, self.radius(transform) + radius, maxDistance);
+		}
+		castPolygon(origin, direction, &self.vertices(transform), radius, maxDistance)
+	}
+	
+	/// Narrow phase test dispatched on both shapes
+	pub fn collide(shape1: &Shape, transform1: &Transform, shape2: &Shape, transform2: &Transform) -> Option<Contact> {
+		match (shape1.isCircle(), shape2.isCircle()) {
+			(true, true) => circleCircle(transform1.position, shape1.radius(transform1), transform2.position, shape2.radius(transform2)),
+			(true, false) => circlePolygon(transform1.position, shape1.radius(transform1), &shape2.vertices(transform2)),
+			(false, true) => circlePolygon(transform2.position, shape2.radius(transform2), &shape1.vertices(transform1))
+				.map(|contact| Contact { normal: -contact.normal, ..contact }),
+			(false, false) => polygonPolygon(&shape1.vertices(transform1), &shape2.vertices(transform2)),
+		}
+	}
+}
+
+/// Smallest box around the vertices, empty at the origin without any
+pub fn polygonBounds(vertices: &[Vec3]) -> AABB {
+	let mut min = vertices.first().copied().unwrap_or_default();
+	let mut max = min;
+	for vertex in vertices.iter() {
+		min = min.min(*vertex);
+		max = max.max(*vertex);
+	}
+	AABB::new(min, max - min)
+}
+
+pub fn signedArea(vertices: &[Vec3]) -> f32 {
+	let mut area = 0.0;
+	for i in 0..vertices.len() {
+		let a = vertices[i];
+		let b = vertices[(i + 1) % vertices.len()];
+		area += a.x * b.y - b.x * a.y;
+	}
+	area / 2.0
+}
+
+/// Every turn bends the same way and the polygon has an area, in either winding order
+pub fn isConvex(vertices: &[Vec3]) -> bool {
+	if vertices.len() < 3 {
+		return false;
+	}
+	let area = signedArea(vertices);
+	if area.abs() <= f32::EPSILON {
+		return false;
+	}
+	let winding = area.signum();
+	(0..vertices.len()).all(|i| {
+		let a = vertices[i];
+		let b = vertices[(i + 1) % vertices.len()];
+		let c = vertices[(i + 2) % vertices.len()];
+		let (ab, bc) = (b - a, c - b);
+		(ab.x * bc.y - ab.y * bc.x) * winding >= 0.0
+	})
+}
+
+/// Outward normal of the edge `a -> b` on a counter-clockwise polygon
+pub fn edgeNormal(a: Vec3, b: Vec3) -> Vec3 {
+	let edge = b - a;
+	vec3(edge.y, -edge.x, 0.0).normalize_or_zero()
+}
+
+/// Counter-clockwise convex polygon with every edge pushed out by `margin`
+pub fn growPolygon(vertices: &[Vec3], margin: f32) -> Vec<Vec3> {
+	let count = vertices.len();
+	(0..count).map(|i| {
+		let previous = edgeNormal(vertices[(i + count - 1) % count], vertices[i]);
+		let next = edgeNormal(vertices[i], vertices[(i + 1) % count]);
+		// Where the two pushed out edges meet
+		let denominator = 1.0 + previous.dot(next);
+		if denominator <= f32::EPSILON {
+			return vertices[i];
+		}
+		vertices[i] + (previous + next) * (margin / denominator)
+	}).collect()
+}
+
+pub fn closestPointOnSegment(a: Vec3, b: Vec3, point: Vec3) -> Vec3 {
+	let ab = b - a;
+	let lengthSq = ab.length_squared();
+	if lengthSq <= f32::EPSILON {
+		return a;
+	}
+	let t = ((point - a).dot(ab) / lengthSq).clamp(0.0, 1.0);
+	a + ab * t
+}
+
+pub fn polygonContainsPoint(vertices: &[Vec3], point: Vec3) -> bool {
+	if vertices.len() < 3 {
+		return false;
+	}
+	for i in 0..vertices.len() {
+		let a = vertices[i];
+		let b = vertices[(i + 1) % vertices.len()];
+		if edgeNormal(a, b).dot(point - a) > 0.0 {
+			return false;
+		}
+	}
+	true
+}
+
+pub fn circleCircle(center1: Vec3, radius1: f32, center2: Vec3, radius2: f32) -> Option<Contact> {
+	let dir = center1 - center2;
+	let dist = dir.length();
+	let minDist = radius1 + radius2;
+	if dist >= minDist {
+		return None;
+	}
+	
+	let mut normal = dir.normalize_or_zero();
+	if dist <= f32::EPSILON {
+		normal = Vec3::X;
+	}
+	Some(Contact {
+		point: center1 - normal * radius1,
+		normal,
+		depth: minDist - dist,
+	})
+}
+
+/// Circle against a counter-clockwise polygon, the normal pushes the circle out
+pub fn circlePolygon(center: Vec3, radius: f32, vertices: &[Vec3]) -> Option<Contact> {
+	if vertices.len() < 2 {
+		return None;
+	}
+	
+	// Deepest face separation tells whether the center is inside
+	let mut maxSeparation = f32::MIN;
+	let mut faceNormal = Vec3::X;
+	for i in 0..vertices.len() {
+		let a = vertices[i];
+		let b = vertices[(i + 1) % vertices.len()];
+		let normal = edgeNormal(a, b);
+		let separation = normal.dot(center - a);
+		if separation > radius {
+			return None;
+		}
+		if separation > maxSeparation {
+			maxSeparation = separation;
+			faceNormal = normal;
+		}
+	}
+	
+	if maxSeparation < 0.0 {
+		return Some(Contact {
+			point: center - faceNormal * maxSeparation,
+			normal: faceNormal,
+			depth: radius - maxSeparation,
+		});
+	}
+	
+	let mut closest = vertices[0];
+	let mut closestDistSq = f32::MAX;
+	for i in 0..vertices.len() {
+		let point = closestPointOnSegment(vertices[i], vertices[(i + 1) % vertices.len()], center);
+		let distSq = point.distance_squared(center);
+		if distSq < closestDistSq {
+			closestDistSq = distSq;
+			closest = point;
+		}
+	}
+	
+	let dist = closestDistSq.sqrt();
+	if dist >= radius {
+		return None;
+	}
+	let normal = if dist <= f32::EPSILON { faceNormal } else { (center - closest) / dist };
+	Some(Contact {
+		point: closest,
+		normal,
+		depth: radius - dist,
+	})
+}
+
+fn cross(a: Vec3, b: Vec3) -> f32 {
+	a.x * b.y - a.y * b.x
+}
+
+/// Distance along a unit ray to the segment `a -> b`
+pub fn castSegment(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3) -> Option<f32> {
+	let edge = b - a;
+	let denominator = cross(direction, edge);
+	if denominator.abs() <= f32::EPSILON {
+		return None;
+	}
+	
+	let toStart = a - origin;
+	let distance = cross(toStart, edge) / denominator;
+	let along = cross(toStart, direction) / denominator;
+	if distance < 0.0 || !(0.0..=1.0).contains(&along) {
+		return None;
+	}
+	Some(distance)
+}
+
+/// Unit ray against a circle, starting inside is a hit at zero unless heading out
+pub fn castCircle(origin: Vec3, direction: Vec3, center: Vec3, radius: f32, maxDistance: f32) -> Option<(f32, Vec3)> {
+	let offset = origin - center;
+	let c = offset.length_squared() - radius * radius;
+	let b = offset.dot(direction);
+	if c <= 0.0 {
+		return (b < 0.0).then(|| (0.0, offset.normalize_or_zero()));
+	}
+	if b >= 0.0 {
+		return None;
+	}
+	
+	let discriminant = b * b - c;
+	if discriminant < 0.0 {
+		return None;
+	}
+	let distance = -b - discriminant.sqrt();
+	if distance > maxDistance {
+		return None;
+	}
+	Some((distance, (offset + direction * distance).normalize_or_zero()))
+}
+
+/// Unit ray against a counter-clockwise polygon grown by `radius`, starting inside is a hit at zero unless heading out
+pub fn castPolygon(origin: Vec3, direction: Vec3, vertices: &[Vec3], radius: f32, maxDistance: f32) -> Option<(f32, Vec3)> {
+	if vertices.len() < 2 {
+		return None;
+	}
+	if let Some(contact) = circlePolygon(origin, radius, vertices) {
+		return (contact.normal.dot(direction) < 0.0).then_some((0.0, contact.normal));
+	}
+	
+	// Grown polygon is the edges pushed out by the radius with rounded corners
+	let mut closest: Option<(f32, Vec3)> = None;
+	for i in 0..vertices.len() {
+		let a = vertices[i];
+		let b = vertices[(i + 1) % vertices.len()];
+		let normal = edgeNormal(a, b);
+		if normal.dot(direction) < 0.0
+			&& let Some(distance) = castSegment(origin, direction, a + normal * radius, b + normal * radius)
+			&& distance <= maxDistance
+			&& closest.is_none_or(|(closest, _)| distance < closest) {
+			closest = Some((distance, normal));
+		}
+		if radius > 0.0
+			&& let Some((distance, normal)) = castCircle(origin, direction, a, radius, maxDistance)
+			&& closest.is_none_or(|(closest, _)| distance < closest) {
+			closest = Some((distance, normal));
+		}
+	}
+	closest
+}
+
+fn project(vertices: &[Vec3], axis: Vec3) -> (f32, f32) {
+	let mut min = f32::MAX;
+	let mut max = f32::MIN;
+	for vertex in vertices.iter() {
+		let p = vertex.dot(axis);
+		min = min.min(p);
+		max = max.max(p);
+	}
+	(min, max)
+}
+
+fn centroid(vertices: &[Vec3]) -> Vec3 {
+	vertices.iter().fold(Vec3::ZERO, |sum, v| sum + *v) / vertices.len() as f32
+}
+
+/// Separating axis test between two counter-clockwise polygons
+pub fn polygonPolygon(vertices1: &[Vec3], vertices2: &[Vec3]) -> Option<Contact> {
+	if vertices1.len() < 2 || vertices2.len() < 2 {
+		return None;
+	}
+	
+	let mut depth = f32::MAX;
+	let mut normal = Vec3::X;
+	for vertices in [vertices1, vertices2] {
+		for i in 0..vertices.len() {
+			let axis = edgeNormal(vertices[i], vertices[(i + 1) % vertices.len()]);
+			if axis == Vec3::ZERO {
+				continue;
+			}
+			let (min1, max1) = project(vertices1, axis);
+			let (min2, max2) = project(vertices2, axis);
+			let overlap = (max1 - min2).min(max2 - min1);
+			if overlap <= 0.0 {
+				return None;
+			}
+			if overlap < depth {
+				depth = overlap;
+				normal = axis;
+			}
+		}
+	}
+	
+	if (centroid(vertices1) - centroid(vertices2)).dot(normal) < 0.0 {
+		normal = -normal;
+	}
+	
+	// Vertex of the first polygon reaching furthest into the second
+	let point = vertices1.iter()
+		.copied()
+		.min_by(|a, b| a.dot(normal).total_cmp(&b.dot(normal)))
+		.unwrap();
+	Some(Contact {
+		point,
+		normal,
+		depth,
+	})
+}
@@ -1,18 +1,38 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::f32::consts::TAU;
 use std::fmt::Debug;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
 use bool_flags::Flags8;
+#[cfg(feature = "graphics")]
 use dear_imgui_rs::{TreeNodeFlags, Ui, WindowFlags};
-use glam::{vec3, Mat4, Vec3};
+use glam::Vec3;
 use tracing::info;
-use crate::graphics::{LineRenderer, Renderable};
-use crate::graphics::mesh::{Mesh, Vertex};
-use crate::simulation::region::{BSPGrid, AABB};
+use crate::simulation::ball::Ball;
+use crate::simulation::constraint::{Constraint, DistanceConstraint, PressureConstraint, SpringConstraint};
+use crate::simulation::constraint_box::ConstraintBox;
+use crate::simulation::contact::{diffContacts, ContactEvent, ContactListener, PhysicalContact};
+use crate::simulation::container::Container;
+#[cfg(feature = "graphics")]
+use crate::simulation::emitter::Emitter;
+#[cfg(feature = "graphics")]
+use crate::simulation::force_field::{Attractor, Drag, RegionField, Vortex, Wind};
+use crate::simulation::force_field::ForceField;
+use crate::simulation::fluid::FluidSettings;
+use crate::simulation::generator::{ClothOptions, RopeOptions};
+#[cfg(feature = "graphics")]
+use crate::simulation::obstacle::Obstacle;
+#[cfg(feature = "graphics")]
+use crate::simulation::sensor::{KillZone, Sensor, SensorArea};
+use crate::simulation::region::{BSPGrid, UniformGrid, AABB};
+use crate::simulation::shape::{polygonBounds, Shape};
 use crate::simulation::Transform;
 use crate::thread_pool::ThreadPool;
-use crate::types::{newMeshRef, GlRef, MeshRef, PhysicalRef, ShaderRef};
+#[cfg(feature = "graphics")]
+use crate::types::{newEmitterRef, newForceFieldRef, newKillZoneRef, newObstacleRef, newSensorRef};
+use crate::types::{newConstraintRef, newPhysicalRef, ConstraintRef, EmitterRef, ForceFieldRef, KillZoneRef, ObstacleRef, PhysicalRef, SensorRef};
 
 static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -34,23 +54,111 @@ pub trait Physical: Debug + Send + Sync {
 	
 	fn fixed(&self) -> bool;
 	
+	fn setFixed(&mut self, fixed: bool);
+	
+	fn pin(&mut self) {
+		self.setFixed(true);
+	}
+	
+	fn unpin(&mut self) {
+		self.setFixed(false);
+	}
+	
+	/// Sleeping bodies skip integration and act as fixed until woken by a contact or a velocity change
+	fn sleeping(&self) -> bool;
+	
+	/// Falling asleep drops any velocity, waking up starts the rest timer over
+	fn setSleeping(&mut self, sleeping: bool);
+	
+	/// Time spent moving slower than the sleep threshold
+	fn restTime(&self) -> f32;
+	
+	fn setRestTime(&mut self, time: f32);
+	
+	/// Seconds left before the solver removes this physical, `None` to live forever
+	fn lifetime(&self) -> Option<f32>;
+	
+	fn setLifetime(&mut self, lifetime: Option<f32>);
+	
+	/// Fluid particles are pushed apart by the solver's density pass instead of colliding with each other
+	fn fluid(&self) -> bool;
+	
+	fn setFluid(&mut self, fluid: bool);
+	
 	fn update(&mut self, dt: f32);
 	
+	/// Wakes a sleeping body, anything pushing it is a reason to move
 	fn accelerate(&mut self, acceleration: Vec3);
 	
+	/// Accelerate by `force / mass`
+	fn applyForce(&mut self, force: Vec3) {
+		let mass = self.mass();
+		if mass > 0.0 {
+			self.accelerate(force / mass);
+		}
+	}
+	
 	fn setVelocity(&mut self, velocity: Vec3, dt: f32);
 	
 	fn addVelocity(&mut self, velocity: Vec3, dt: f32);
 	
 	fn getVelocity(&self, dt: f32) -> Vec3;
 	
+	/// Collision layer bits this physical is on
+	fn layer(&self) -> u32;
+	
+	/// Layers this physical collides with, boundaries ignore both
+	fn mask(&self) -> u32;
+	
+	/// Both have to accept the other's layer
+	fn collidesWith(&self, other: &dyn Physical) -> bool {
+		self.layer() & other.mask() != 0 && other.layer() & self.mask() != 0
+	}
+	
 	fn elasticity(&self) -> f32; // todo: try moving properties to separate component
 	
-	fn color(&self) -> Vec3; // todo: move shape and collision to separate components
+	/// Move `depth` along `normal` out of a static surface, bouncing the velocity going into it by elasticity
+	fn pushOut(&mut self, normal: Vec3, depth: f32) {
+		let mut velocity = self.transform().position - self.lastTransform().position;
+		self.transformMut().position += normal * depth;
+		let inward = velocity.dot(normal);
+		if inward < 0.0 {
+			velocity -= normal * inward * (1.0 + self.elasticity());
+		}
+		let position = self.transform().position;
+		self.lastTransformMut().position = position - velocity;
+	}
+	
+	fn mass(&self) -> f32;
+	
+	/// Zero for fixed and sleeping bodies so they act as infinitely heavy
+	fn inverseMass(&self) -> f32 {
+		let mass = self.mass();
+		if self.fixed() || self.sleeping() || mass <= 0.0 {
+			0.0
+		} else {
+			1.0 / mass
+		}
+	}
+	
+	fn color(&self) -> Vec3;
+	
+	fn shape(&self) -> &Shape;
 	
 	fn bounds(&self) -> AABB;
 }
 
+/// Closest physical stopping a ray or swept circle
+#[derive(Copy, Clone, Debug)]
+pub struct RayHit {
+	pub id: usize,
+	/// Contact point on the hit physical's surface
+	pub point: Vec3,
+	pub normal: Vec3,
+	/// Distance travelled along the ray
+	pub distance: f32,
+}
+
 struct Edge {
 	id: usize,
 	isMinimum: bool,
@@ -58,9 +166,29 @@ struct Edge {
 }
 
 struct Chunk {
+	/// Position in `Solver::chunks`
+	index: usize,
 	tree: BSPGrid<PhysicalRef>,
 	physicals: Vec<PhysicalRef>,
 	neighbours: Vec<ChunkRef>,
+	/// Shared by every chunk, see `Solver::sleepingTree`
+	sleeping: Arc<BSPGrid<PhysicalRef>>,
+}
+
+impl Chunk {
+	/// Found by dividing instead of comparing against bounds, where rounding can put a shared edge in both chunks
+	fn indexOf(position: Vec3, worldSize: Vec3) -> Option<usize> {
+		let cell = ((position + worldSize / 2.0) / worldSize * CHUNK_COUNT as f32).floor();
+		if cell.x < 0.0 || cell.y < 0.0 || cell.x >= CHUNK_COUNT as f32 || cell.y >= CHUNK_COUNT as f32 {
+			return None;
+		}
+		// Rows are stored top down
+		Some(cell.x as usize + (CHUNK_COUNT - 1 - cell.y as usize) * CHUNK_COUNT)
+	}
+	
+	fn owns(&self, position: Vec3, worldSize: Vec3) -> bool {
+		Self::indexOf(position, worldSize) == Some(self.index)
+	}
 }
 
 type ChunkRef = Arc<RwLock<Chunk>>;
@@ -71,17 +199,33 @@ const F_FORCE_STEP: u8 = 2;
 
 const F_COLLISION_MODE: u8 = 3;
 const F_THREAD_MODE: u8 = 4;
+const F_DETERMINISTIC: u8 = 5;
+const F_CCD: u8 = 6;
+const F_SLEEP: u8 = 7;
 
 const GRID_CAPACITY: usize = 2;
+const SOFT_BODY_STIFFNESS: f32 = 5000.0;
+const SOFT_BODY_DAMPING: f32 = 20.0;
+const SOFT_BODY_PRESSURE: f32 = 2000.0;
+/// Default speed a body has to stay under for `SLEEP_TIME` seconds to fall asleep
+const SLEEP_SPEED: f32 = 10.0;
+const SLEEP_TIME: f32 = 0.5;
+/// Begin and end events kept for the gui log
+const CONTACT_LOG_SIZE: usize = 32;
+/// Obstacle grid cells along each axis
+const OBSTACLE_CELLS: usize = 32;
 const THREAD_COUNT: usize = 12;
+/// Chunks along each axis, independent of how many threads the machine gives us
+const CHUNK_COUNT: usize = THREAD_COUNT;
 static U64_ATOMIC_BUFFER: AtomicU64 = AtomicU64::new(0);
 
 pub struct Solver {
-	mesh: MeshRef,
-	shader: ShaderRef,
-	
 	pub gravity: Vec3,
 	pub worldSize: Vec3,
+	pub fluid: FluidSettings,
+	container: Container,
+	/// Gravity sleeping bodies came to rest under, a change wakes everything
+	restGravity: Vec3,
 	
 	threadPool: ThreadPool,
 	chunks: Vec<ChunkRef>,
@@ -89,7 +233,43 @@ pub struct Solver {
 	edgesX: Vec<Edge>,
 	edgesY: Vec<Edge>,
 	quadTree: BSPGrid<PhysicalRef>,
-	physicals: HashMap<usize, PhysicalRef>,
+	physicals: BTreeMap<usize, PhysicalRef>,
+	constraints: Vec<ConstraintRef>,
+	boxes: Vec<ConstraintBox>,
+	forceFields: Vec<ForceFieldRef>,
+	obstacles: Vec<ObstacleRef>,
+	/// Rebuilt whenever obstacles change and shared with the chunk jobs
+	obstacleGrid: Arc<UniformGrid<ObstacleRef>>,
+	sensors: Vec<SensorRef>,
+	emitters: Vec<EmitterRef>,
+	killZones: Vec<KillZoneRef>,
+	/// Physicals removed for running out of lifetime
+	expired: usize,
+	/// Physicals removed by kill zones
+	killed: usize,
+	maxBoundingRadius: f32,
+	/// Contacts resolved this update, filled from the chunk jobs too
+	foundContacts: Arc<Mutex<Vec<PhysicalContact>>>,
+	/// Pairs touching at the end of the last update
+	touching: HashMap<(usize, usize), PhysicalContact>,
+	contactListeners: Vec<(usize, ContactListener)>,
+	contactLog: VecDeque<ContactEvent>,
+	/// Begin, persist and end events sent last update
+	contactEventCounts: [usize; 3],
+	/// Furthest anything may have moved since the trees were built
+	treeDrift: f32,
+	/// Physicals outside the world when the trees were built, queries check these one by one
+	outsideTrees: Vec<PhysicalRef>,
+	/// Sleeping physicals are left out of the per update trees and kept here instead,
+	/// only rebuilt when something falls asleep or wakes up
+	sleepingTree: Arc<BSPGrid<PhysicalRef>>,
+	/// Ids in `sleepingTree` or `sleepingOutside`, in id order
+	sleepingIds: Vec<usize>,
+	/// Sleeping physicals outside the world, these stay with the awake ones
+	sleepingOutside: Vec<PhysicalRef>,
+	/// Speed a body has to stay under for `sleepTime` seconds to fall asleep
+	pub sleepSpeed: f32,
+	pub sleepTime: f32,
 	
 	subSteps: u32,
 	updatesDone: u32,
@@ -115,65 +295,42 @@ pub struct Solver {
 }
 
 impl Solver {
-	pub fn new(worldSize: Vec3, gl: GlRef, shader: ShaderRef) -> Result<Solver, String> {
+	pub fn new(worldSize: Vec3) -> Solver {
 		let worldSize = worldSize.truncate().extend(0.0); // Only simulating 2d for now
 		
-		let mesh = {
-			let vertices = vec![
-				Vertex {
-					position: vec3(-0.5, 0.5, 0.0),
-					color: Vec3::splat(0.15),
-				},
-				Vertex {
-					position: vec3(0.5, 0.5, 0.0),
-					color: Vec3::splat(0.15),
-				},
-				Vertex {
-					position: vec3(0.5, -0.5, 0.0),
-					color: Vec3::splat(0.15),
-				},
-				Vertex {
-					position: vec3(-0.5, -0.5, 0.0),
-					color: Vec3::splat(0.15),
-				}
-			];
-			let indices = vec![0, 1, 2, 2, 3, 0];
-			let mut mesh = Mesh::simple(gl, vertices, Some(indices));
-			mesh.upload(shader.clone())?;
-			newMeshRef(mesh)
-		};
-		
 		let threadPool = ThreadPool::withNWorkers(THREAD_COUNT);
 		
 		info!("Creating solver chunks");
-		let mut chunks = Vec::with_capacity(threadPool.getTotal() * threadPool.getTotal());
-		let chunkSize = worldSize / threadPool.getTotal() as f32;
+		let mut chunks = Vec::with_capacity(CHUNK_COUNT * CHUNK_COUNT);
+		let chunkSize = worldSize / CHUNK_COUNT as f32;
 		let worldSizeHalf = worldSize / 2.0;
 		
-		for y in 0..threadPool.getTotal() {
-			for x in 0..threadPool.getTotal() {
-				let pos = Vec3::new(x as f32 * chunkSize.x, (threadPool.getTotal() - 1 - y) as f32 * chunkSize.y, 0.0) - worldSizeHalf;
+		for y in 0..CHUNK_COUNT {
+			for x in 0..CHUNK_COUNT {
+				let pos = Vec3::new(x as f32 * chunkSize.x, (CHUNK_COUNT - 1 - y) as f32 * chunkSize.y, 0.0) - worldSizeHalf;
 				let bounds = AABB::new(pos, chunkSize);
 				chunks.push(Arc::new(RwLock::new(Chunk {
+					index: x + y * CHUNK_COUNT,
 					tree: BSPGrid::new(GRID_CAPACITY, bounds),
 					physicals: Vec::new(),
 					neighbours: Vec::new(),
+					sleeping: Arc::new(BSPGrid::new(GRID_CAPACITY, AABB::centered(Vec3::ZERO, worldSize))),
 				})));
 			}
 		}
 		
 		info!("Finding chunk neighbours");
-		for x in 0..threadPool.getTotal() {
-			for y in 0..threadPool.getTotal() {
-				let chunk = chunks[x + y * threadPool.getTotal()].clone();
+		for x in 0..CHUNK_COUNT {
+			for y in 0..CHUNK_COUNT {
+				let chunk = chunks[x + y * CHUNK_COUNT].clone();
 				for dy in -1..2 {
 					for dx in -1..2 {
 						let x = dx + x as i32;
 						let y = dy + y as i32;
-						if (dx == 0 && dy == 0) || (x < 0 || x >= threadPool.getTotal() as i32 || y < 0 || y >= threadPool.getTotal() as i32) {
+						if (dx == 0 && dy == 0) || (x < 0 || x >= CHUNK_COUNT as i32 || y < 0 || y >= CHUNK_COUNT as i32) {
 							continue;
 						}
-						chunk.write().unwrap().neighbours.push(chunks[x as usize + y as usize * threadPool.getTotal()].clone());
+						chunk.write().unwrap().neighbours.push(chunks[x as usize + y as usize * CHUNK_COUNT].clone());
 					}
 				}
 			}
@@ -183,12 +340,13 @@ impl Solver {
 		flags.set(F_PAUSED);
 		flags.set(F_COLLISION_MODE);
 		flags.set(F_THREAD_MODE);
-		Ok(Self {
-			mesh,
-			shader,
-			
+		flags.set(F_SLEEP);
+		Self {
 			gravity: Vec3::ZERO,
 			worldSize,
+			fluid: FluidSettings::default(),
+			container: Container::Box,
+			restGravity: Vec3::ZERO,
 			
 			threadPool,
 			chunks,
@@ -196,7 +354,30 @@ impl Solver {
 			edgesX: Vec::new(),
 			edgesY: Vec::new(),
 			quadTree: BSPGrid::new(GRID_CAPACITY, AABB::centered(Vec3::ZERO, worldSize)), // todo: fix vec3 issue with aabb/quadtree
-			physicals: HashMap::new(),
+			physicals: BTreeMap::new(),
+			constraints: Vec::new(),
+			boxes: Vec::new(),
+			forceFields: Vec::new(),
+			obstacles: Vec::new(),
+			obstacleGrid: Arc::new(UniformGrid::new(OBSTACLE_CELLS, AABB::centered(Vec3::ZERO, worldSize))),
+			sensors: Vec::new(),
+			emitters: Vec::new(),
+			killZones: Vec::new(),
+			expired: 0,
+			killed: 0,
+			maxBoundingRadius: 0.0,
+			foundContacts: Arc::new(Mutex::new(Vec::new())),
+			touching: HashMap::new(),
+			contactListeners: Vec::new(),
+			contactLog: VecDeque::with_capacity(CONTACT_LOG_SIZE),
+			contactEventCounts: [0; 3],
+			treeDrift: 0.0,
+			outsideTrees: Vec::new(),
+			sleepingTree: Arc::new(BSPGrid::new(GRID_CAPACITY, AABB::centered(Vec3::ZERO, worldSize))),
+			sleepingIds: Vec::new(),
+			sleepingOutside: Vec::new(),
+			sleepSpeed: SLEEP_SPEED,
+			sleepTime: SLEEP_TIME,
 			
 			subSteps: 8,
 			updatesDone: 0,
@@ -219,13 +400,87 @@ impl Solver {
 			chunkBuildTime: 0.0,
 			
 			stepTime: 0.0,
-		})
+		}
 	}
 	
 	pub fn isDestroyed(&self) -> bool {
 		self.flags.get(F_DESTROYED)
 	}
 	
+	pub fn isThreadMode(&self) -> bool {
+		self.flags.get(F_THREAD_MODE)
+	}
+	
+	pub fn setThreadMode(&mut self, threaded: bool) {
+		if threaded {
+			self.flags.set(F_THREAD_MODE);
+		} else {
+			self.flags.clear(F_THREAD_MODE);
+		}
+	}
+	
+	/// Fixed iteration order and no skipped contacts, same scene and settings give the same state every run
+	pub fn isDeterministic(&self) -> bool {
+		self.flags.get(F_DETERMINISTIC)
+	}
+	
+	pub fn setDeterministic(&mut self, deterministic: bool) {
+		if deterministic {
+			self.flags.set(F_DETERMINISTIC);
+		} else {
+			self.flags.clear(F_DETERMINISTIC);
+		}
+	}
+	
+	/// Sweep fast balls between sub steps so they can't pass through anything
+	pub fn isCcd(&self) -> bool {
+		self.flags.get(F_CCD)
+	}
+	
+	pub fn setCcd(&mut self, ccd: bool) {
+		if ccd {
+			self.flags.set(F_CCD);
+		} else {
+			self.flags.clear(F_CCD);
+		}
+	}
+	
+	/// Space partition when true, sweep and prune otherwise. Only used without threads
+	pub fn isPartitionMode(&self) -> bool {
+		self.flags.get(F_COLLISION_MODE)
+	}
+	
+	pub fn setPartitionMode(&mut self, partition: bool) {
+		if partition {
+			self.flags.set(F_COLLISION_MODE);
+		} else {
+			self.flags.clear(F_COLLISION_MODE);
+		}
+	}
+	
+	/// Let resting bodies fall asleep, turning it off wakes everything
+	pub fn isSleepMode(&self) -> bool {
+		self.flags.get(F_SLEEP)
+	}
+	
+	pub fn setSleepMode(&mut self, sleep: bool) {
+		if sleep {
+			self.flags.set(F_SLEEP);
+		} else {
+			self.flags.clear(F_SLEEP);
+			self.wakeAll();
+		}
+	}
+	
+	pub fn getContainer(&self) -> &Container {
+		&self.container
+	}
+	
+	pub fn setContainer(&mut self, container: Container) {
+		self.container = container;
+		self.wakeAll();
+	}
+	
 	pub fn isPaused(&self) -> bool {
 		self.flags.get(F_PAUSED)
 	}
@@ -254,291 +509,1217 @@ impl Solver {
 		let id = {
 			let borrow = physical.read().unwrap();
 			let id = borrow.id();
-			let transform = borrow.transform();
+			let bounds = borrow.shape().bounds(borrow.transform());
 			self.edgesX.push(Edge {
 				id,
 				isMinimum: true,
-				coord: transform.position.x - transform.scale.x / 2.0,
+				coord: bounds.start().x,
 			});
 			self.edgesX.push(Edge {
 				id,
 				isMinimum: false,
-				coord: transform.position.x + transform.scale.x / 2.0,
+				coord: bounds.end().x,
 			});
 			self.edgesY.push(Edge {
 				id,
 				isMinimum: true,
-				coord: transform.position.y - transform.scale.y / 2.0,
+				coord: bounds.start().y,
 			});
 			self.edgesY.push(Edge {
 				id,
 				isMinimum: false,
-				coord: transform.position.y + transform.scale.y / 2.0,
+				coord: bounds.end().y,
 			});
+			self.maxBoundingRadius = self.maxBoundingRadius.max(borrow.shape().boundingRadius(borrow.transform()));
 			id
 		};
+		
+		// Trees are rebuilt every update, queries in between should still see new physicals
+		let position = physical.read().unwrap().transform().position;
+		let inserted = self.quadTree.insert(physical.clone(), &|physical, bounds| {
+			bounds.containsPoint(physical.read().unwrap().transform().position)
+		});
+		if !inserted {
+			self.outsideTrees.push(physical.clone());
+		}
+		for chunk in self.chunks.iter() {
+			let mut chunk = chunk.write().unwrap();
+			if chunk.owns(position, self.worldSize) {
+				chunk.tree.insert(physical.clone(), &|physical, bounds| {
+					bounds.containsPoint(physical.read().unwrap().transform().position)
+				});
+				chunk.physicals.push(physical.clone());
+				break;
+			}
+		}
+		
 		self.physicals.insert(id, physical);
 	}
 	
-	// todo: try collision checks with rays
-	fn collideWithPhysical(physical1: PhysicalRef, physical2: PhysicalRef) {
-		if let Ok(mut physical1) = physical1.try_write() {
-			if let Ok(mut physical2) = physical2.try_write() {
-				let r1 = physical1.transform().scale.x / 2.0;
-				let r2 = physical2.transform().scale.x / 2.0;
-				
-				let dir = physical1.transform().position - physical2.transform().position;
-				let dist = dir.length();
-				let minDist = r1 + r2;
-				if dist < minDist {
-					let mut dir = dir.normalize_or_zero();
-					if dist <= f32::EPSILON {
-						dir = Vec3::X;
-					}
-					
-					let massRatio1 = r1 / minDist;
-					let massRatio2 = r2 / minDist;
-					let force = ((physical1.elasticity() + physical2.elasticity()) / 2.0) / 2.0 * (dist - minDist);
-					
-					if !physical1.fixed() {
-						physical1.transformMut().position -= dir * massRatio2 * force;
-					}
-					if !physical2.fixed() {
-						physical2.transformMut().position += dir * massRatio1 * force;
-					}
-				}
-			}
+	pub fn removePhysical(&mut self, id: usize) -> Option<PhysicalRef> {
+		if !self.physicals.contains_key(&id) {
+			return None;
 		}
+		self.removeIds(&HashSet::from([id])).pop()
 	}
 	
-	fn collideWithBoundary(_dt: f32, physical: PhysicalRef, worldSize: Vec3) {
-		if let Ok(mut physical) = physical.try_write() {
-			let halfSize = (worldSize - physical.transform().scale.x) / 2.0;
-			let velocity = physical.getVelocity(1.0) * physical.elasticity();
-			
-			if physical.transform().position.x < -halfSize.x {
-				physical.transformMut().position.x = -halfSize.x;
-				physical.lastTransformMut().position.x = -halfSize.x + velocity.x;
-			} else if physical.transform().position.x > halfSize.x {
-				physical.transformMut().position.x = halfSize.x;
-				physical.lastTransformMut().position.x = halfSize.x + velocity.x;
+	/// Remove every physical matching the predicate, returns what was removed
+	pub fn removePhysicals<F: FnMut(&dyn Physical) -> bool>(&mut self, mut predicate: F) -> Vec<PhysicalRef> {
+		let ids: HashSet<usize> = self.physicals.iter()
+			.filter(|(_, physical)| predicate(&*physical.read().unwrap()))
+			.map(|(id, _)| *id)
+			.collect();
+		self.removeIds(&ids)
+	}
+	
+	fn removeIds(&mut self, ids: &HashSet<usize>) -> Vec<PhysicalRef> {
+		if ids.is_empty() {
+			return Vec::new();
+		}
+		let removed: Vec<PhysicalRef> = ids.iter().filter_map(|id| self.physicals.remove(id)).collect();
+		
+		self.edgesX.retain(|edge| !ids.contains(&edge.id));
+		self.edgesY.retain(|edge| !ids.contains(&edge.id));
+		self.outsideTrees.retain(|physical| !ids.contains(&physical.read().unwrap().id()));
+		
+		// Trees are rebuilt every update, but queries in between should not see removed physicals
+		let keep = |physical: &PhysicalRef| !ids.contains(&physical.read().unwrap().id());
+		self.quadTree.retain(&keep);
+		Arc::make_mut(&mut self.sleepingTree).retain(&keep);
+		self.sleepingOutside.retain(keep);
+		self.sleepingIds.retain(|id| !ids.contains(id));
+		for chunk in self.chunks.iter() {
+			let mut chunk = chunk.write().unwrap();
+			chunk.tree.retain(&keep);
+			chunk.physicals.retain(keep);
+		}
+		
+		// Contacts of removed physicals end now, not one update late, and found ones never begin
+		self.foundContacts.lock().unwrap().retain(|contact| !ids.contains(&contact.id1) && !ids.contains(&contact.id2));
+		let mut ended: Vec<ContactEvent> = self.touching.values()
+			.filter(|contact| ids.contains(&contact.id1) || ids.contains(&contact.id2))
+			.map(|contact| ContactEvent::End(*contact))
+			.collect();
+		self.touching.retain(|(id1, id2), _| !ids.contains(id1) && !ids.contains(id2));
+		ended.sort_by_key(|event| event.contact().key());
+		self.sendContactEvents(&ended);
+		
+		// A box without all of its corners is just loose particles
+		let mut constraintIds = HashSet::new();
+		self.boxes.retain(|constraintBox| {
+			if constraintBox.corners.iter().any(|id| ids.contains(id)) {
+				constraintIds.extend(constraintBox.constraints.iter().copied());
+				return false;
 			}
-			
-			if physical.transform().position.y < -halfSize.y {
-				physical.transformMut().position.y = -halfSize.y;
-				physical.lastTransformMut().position.y = -halfSize.y + velocity.y;
-			} else if physical.transform().position.y > halfSize.y {
-				physical.transformMut().position.y = halfSize.y;
-				physical.lastTransformMut().position.y = halfSize.y + velocity.y;
+			true
+		});
+		self.constraints.retain(|constraint| {
+			let constraint = constraint.read().unwrap();
+			!constraintIds.contains(&constraint.id()) && !constraint.links().iter().any(|(id1, id2)| ids.contains(id1) || ids.contains(id2))
+		});
+		
+		// Anything resting on the removed physicals has to fall
+		for physical in removed.iter() {
+			let area = physical.read().unwrap().bounds().grow(Vec3::splat(2.0));
+			for neighbour in self.findInArea(&area) {
+				let mut neighbour = neighbour.write().unwrap();
+				if neighbour.sleeping() {
+					neighbour.setSleeping(false);
+				}
 			}
 		}
+		removed
 	}
 	
-	fn insertionSort<T, F>(vec: &mut Vec<T>, mut compare: F)
-	where
-		F: FnMut(&T, &T) -> bool,
-	{
-		// Taken with 800 physicals and 1,-400 gravity
-		// ~10.930447ms first sort
-		// ~0.15062812ms second sort and onwards
-		let n = 1..vec.len();
-		for i in n {
-			let mut j = i;
-			while j > 0 && compare(&vec[j], &vec[j - 1]) {
-				vec.swap(j, j - 1);
-				j -= 1;
-			}
+	/// Fix a physical in place, returns false if the id is unknown
+	pub fn pin(&self, id: usize) -> bool {
+		match self.physicals.get(&id) {
+			Some(physical) => {
+				physical.write().unwrap().pin();
+				true
+			},
+			None => false,
 		}
-	
-		// Taken with 800 physicals and 1,-400 gravity
-		// ~10.008063ms first
-		// ~2.7993727ms second-fifth
-		// lowers afterwards
-		// for i in 1..vec.len() {
-		// 	for j in (0..i).rev() {// j=i-1; j >= 0; j--
-		// 		if compare(&vec[j], &vec[j + 1]) {
-		// 			break;
-		// 		}
-		// 		vec.swap(j, j + 1);
-		// 	}
-		// }
 	}
 	
-	fn sweepEdges(edges: &Vec<Edge>) -> Vec<(usize, usize)> {
-		let mut pairs = Vec::<(usize, usize)>::new();
+	pub fn unpin(&self, id: usize) -> bool {
+		match self.physicals.get(&id) {
+			Some(physical) => {
+				let mut physical = physical.write().unwrap();
+				physical.unpin();
+				physical.setSleeping(false);
+				true
+			},
+			None => false,
+		}
+	}
 	
-		let mut touching = Vec::<usize>::new();
-		for edge in edges.iter() {
-			let edgeId = edge.id;
-			if edge.isMinimum {
-				for other in touching.iter() {
-					let other = *other;
-					pairs.push((other.min(edgeId), edgeId.max(other)));
-				}
-				touching.push(edgeId);
-			} else {
-				if let Some(index) = touching.iter().position(|x| *x == edgeId) {
-					touching.remove(index);
+	/// Wake a sleeping physical, returns false if the id is unknown
+	pub fn wake(&self, id: usize) -> bool {
+		match self.physicals.get(&id) {
+			Some(physical) => {
+				let mut physical = physical.write().unwrap();
+				if physical.sleeping() {
+					physical.setSleeping(false);
 				}
-				// touching.retain(|x| *x != edgeId);
-			}
+				true
+			},
+			None => false,
 		}
-	
-		pairs
 	}
 	
-	fn calcVarianceForEdges(edges: &Vec<Edge>) -> f32 {
-		let mut sum = 0.0;
-		for edge in edges.iter() {
-			sum += edge.coord;
+	pub fn wakeAll(&self) {
+		for physical in self.physicals.values() {
+			let mut physical = physical.write().unwrap();
+			if physical.sleeping() {
+				physical.setSleeping(false);
+			}
 		}
-		let mean = sum / edges.len() as f32;
+	}
 	
-		let mut squaredDiffSum = 0.0;
-		for edge in edges.iter() {
-			let diff = edge.coord - mean;
-			squaredDiffSum += diff * diff;
-		}
+	pub fn sleepingCount(&self) -> usize {
+		self.physicals.values().filter(|physical| physical.read().unwrap().sleeping()).count()
+	}
 	
-		squaredDiffSum / edges.len() as f32
+	pub fn addConstraint(&mut self, constraint: ConstraintRef) {
+		self.constraints.push(constraint);
 	}
 	
-	fn calcEdgeCoords(&mut self) {
-		let now = Instant::now();
+	pub fn removeConstraint(&mut self, id: usize) -> bool {
+		let len = self.constraints.len();
+		self.constraints.retain(|constraint| constraint.read().unwrap().id() != id);
+		len != self.constraints.len()
+	}
 	
-		for i in 0..self.edgesX.len() {
-			let edgeX = &mut self.edgesX[i];
-			let (px, sx) = {
-				let borrow = self.physicals[&edgeX.id].read().unwrap();
-				let transform = borrow.transform();
-				(transform.position.x, transform.scale.x / 2.0)
-			};
-			if edgeX.isMinimum {
-				edgeX.coord = px - sx;
-			} else {
-				edgeX.coord = px + sx;
-			}
+	pub fn getConstraint(&self, id: usize) -> Option<ConstraintRef> {
+		self.constraints.iter().find(|constraint| constraint.read().unwrap().id() == id).cloned()
+	}
 	
-			let edgeY = &mut self.edgesY[i];
-			let (py, sy) = {
-				let borrow = self.physicals[&edgeY.id].read().unwrap();
-				let transform = borrow.transform();
-				(transform.position.y, transform.scale.y / 2.0)
-			};
-			if edgeY.isMinimum {
-				edgeY.coord = py - sy;
-			} else {
-				edgeY.coord = py + sy;
-			}
+	/// Edit a constraint in place if it exists and is of type `C`
+	pub fn editConstraint<C: Constraint, F: FnOnce(&mut C)>(&self, id: usize, edit: F) -> bool {
+		let Some(constraint) = self.getConstraint(id) else {
+			return false;
+		};
+		let mut constraint = constraint.write().unwrap();
+		let links = constraint.links();
+		let constraint: &mut dyn Any = &mut *constraint;
+		match constraint.downcast_mut::<C>() {
+			Some(constraint) => {
+				edit(constraint);
+				for (id1, id2) in links {
+					self.wake(id1);
+					self.wake(id2);
+				}
+				true
+			},
+			None => false,
 		}
-	
-		let end = now.elapsed().as_secs_f32() * 1000.0;
-		self.calcEdgeCoordsAccum += end;
 	}
 	
-	fn broadPhaseCollisionCheck(&mut self) {
-		let now = Instant::now();
-	
-		Self::insertionSort(&mut self.edgesX, |a, b| a.coord < b.coord);
-		Self::insertionSort(&mut self.edgesY, |a, b| a.coord < b.coord);
-	
-		let end = now.elapsed().as_secs_f32() * 1000.0;
-		self.sortTimeAccum += end;
+	pub fn addForceField(&mut self, field: ForceFieldRef) {
+		self.forceFields.push(field);
+		self.wakeAll();
+	}
 	
-		let now = Instant::now();
+	pub fn removeForceField(&mut self, id: usize) -> bool {
+		let len = self.forceFields.len();
+		self.forceFields.retain(|field| field.read().unwrap().id() != id);
+		self.wakeAll();
+		len != self.forceFields.len()
+	}
 	
-		// let mut touching: Vec<usize> = Vec::new();
-		// for edge in self.edgesX.iter() {
-		// 	let edgeId = edge.physical.borrow().id();
-		// 	if edge.isMinimum {
-		// 		for other in touching.iter() {
-		// 			self.collideWithPhysical(self.physicals.get(&other).unwrap().clone(), edge.physical.clone());
-		// 		}
-		// 		// self.collideWithBoundary(dt, edge.physical.clone());
-		// 		touching.push(edgeId);
-		// 	} else {
-		// 		// if let Some(index) = touching.iter().position(|x| x.borrow().transform().position == edge.physical.borrow().transform().position) {
-		// 		// 	touching.remove(index);
-		// 		// }
-		// 		touching.retain(|x| *x != edgeId);
-		// 	}
-		// }
+	pub fn getForceField(&self, id: usize) -> Option<ForceFieldRef> {
+		self.forceFields.iter().find(|field| field.read().unwrap().id() == id).cloned()
+	}
 	
-		let pairs = {
-			let varianceX = Self::calcVarianceForEdges(&self.edgesX);
-			let varianceY = Self::calcVarianceForEdges(&self.edgesY);
-			if varianceX > varianceY {
-				self.sweepAxis = "X".to_string();
-				Self::sweepEdges(&self.edgesX)
-			} else {
-				self.sweepAxis = "Y".to_string();
-				Self::sweepEdges(&self.edgesY)
-			}
+	/// Edit a force field in place if it exists and is of type `T`
+	pub fn editForceField<T: ForceField, F: FnOnce(&mut T)>(&self, id: usize, edit: F) -> bool {
+		let Some(field) = self.getForceField(id) else {
+			return false;
 		};
-		self.collisionChecks = pairs.len();
+		let mut field = field.write().unwrap();
+		let field: &mut dyn Any = &mut *field;
+		match field.downcast_mut::<T>() {
+			Some(field) => {
+				edit(field);
+				self.wakeAll();
+				true
+			},
+			None => false,
+		}
+	}
 	
-		// let pairsX = Self::sweepEdges(&self.edgesX);//.into_iter().collect::<HashSet<_>>();
-		// let pairsY = self.sweepEdges(&self.edgesY);//.into_iter().collect::<HashSet<_>>();
-		// let pairs = pairsY.into_iter().filter(|x| pairsX.contains(x)).collect::<Vec<_>>();
-		// let pairs = pairsX.intersection(&pairsY).collect::<HashSet<_>>();
+	pub fn addSensor(&mut self, sensor: SensorRef) {
+		self.sensors.push(sensor);
+	}
 	
-		for (a, b) in pairs.into_iter() {
-			let physical1 = self.physicals[&a].clone();
-			let physical2 = self.physicals[&b].clone();
-			Self::collideWithPhysical(physical1, physical2);
-		}
+	pub fn removeSensor(&mut self, id: usize) -> bool {
+		let len = self.sensors.len();
+		self.sensors.retain(|sensor| sensor.read().unwrap().id() != id);
+		len != self.sensors.len()
+	}
 	
-		let end = now.elapsed().as_secs_f32() * 1000.0;
-		self.sweepTimeAccum += end;
+	pub fn getSensor(&self, id: usize) -> Option<SensorRef> {
+		self.sensors.iter().find(|sensor| sensor.read().unwrap().id() == id).cloned()
 	}
 	
-	fn updatePhysicals(&self, dt: f32) {
-		for (_, physical) in self.physicals.iter() {
-			let physical = physical.clone();
-			{
-				let mut physicalMut = physical.write().unwrap();
-				physicalMut.accelerate(self.gravity);
-				// let gravity = (Vec3::ZERO - physicalMut.transform().position).normalize_or_zero() * self.gravity.x;
-				// physicalMut.accelerate(gravity);
-				physicalMut.update(dt);
-			}
-			Self::collideWithBoundary(dt, physical, self.worldSize);
+	fn updateSensors(&self) {
+		for sensor in self.sensors.iter() {
+			let mut sensor = sensor.write().unwrap();
+			let (shape, transform) = sensor.area.shape();
+			let found = self.queryShape(&sensor.area.bounds(), &shape, &transform);
+			sensor.update(found);
 		}
 	}
 	
-	fn populateQuadTree(&mut self) {
-		// 17+ fps
-		// ~3.5ms
-		self.quadTree.clear();
-		for (_, physical) in self.physicals.iter() {
-			self.quadTree.insert(physical.clone(), &|physical, bounds| {
-				bounds.containsPoint(physical.read().unwrap().transform().position)
-			});
-		}
+	pub fn addEmitter(&mut self, emitter: EmitterRef) {
+		self.emitters.push(emitter);
 	}
 	
-	fn subStep(&mut self, dt: f32) {
-		let now = Instant::now();
+	pub fn removeEmitter(&mut self, id: usize) -> bool {
+		let len = self.emitters.len();
+		self.emitters.retain(|emitter| emitter.read().unwrap().id() != id);
+		len != self.emitters.len()
+	}
 	
-		if self.flags.get(F_COLLISION_MODE) {
-			self.populateQuadTree();
+	pub fn getEmitter(&self, id: usize) -> Option<EmitterRef> {
+		self.emitters.iter().find(|emitter| emitter.read().unwrap().id() == id).cloned()
+	}
 	
-			// ~5ms
+	fn updateEmitters(&mut self, dt: f32, subStepDt: f32) {
+		let mut spawned = Vec::new();
+		for emitter in self.emitters.iter() {
+			let occupied = |position, radius| !self.queryCircle(position, radius).is_empty();
+			spawned.append(&mut emitter.write().unwrap().emit(&self.physicals, dt, subStepDt, occupied));
+		}
+		for ball in spawned {
+			self.addPhysical(newPhysicalRef(ball));
+		}
+	}
+	
+	pub fn addKillZone(&mut self, zone: KillZoneRef) {
+		self.killZones.push(zone);
+	}
+	
+	pub fn removeKillZone(&mut self, id: usize) -> bool {
+		let len = self.killZones.len();
+		self.killZones.retain(|zone| zone.read().unwrap().id() != id);
+		len != self.killZones.len()
+	}
+	
+	pub fn getKillZone(&self, id: usize) -> Option<KillZoneRef> {
+		self.killZones.iter().find(|zone| zone.read().unwrap().id() == id).cloned()
+	}
+	
+	pub fn expiredCount(&self) -> usize {
+		self.expired
+	}
+	
+	pub fn killedCount(&self) -> usize {
+		self.killed
+	}
+	
+	/// Count lifetimes down and remove what ran out or overlaps a kill zone
+	fn removeExpired(&mut self, dt: f32) {
+		let mut expired = HashSet::new();
+		for (id, physical) in self.physicals.iter() {
+			let mut physical = physical.write().unwrap();
+			if let Some(lifetime) = physical.lifetime() {
+				physical.setLifetime(Some(lifetime - dt));
+				if lifetime - dt <= 0.0 {
+					expired.insert(*id);
+				}
+			}
+		}
+		
+		let mut killed = HashSet::new();
+		for zone in self.killZones.iter() {
+			let mut zone = zone.write().unwrap();
+			let (shape, transform) = zone.area.shape();
+			let count = self.queryShape(&zone.area.bounds(), &shape, &transform).into_iter()
+				.filter(|id| !expired.contains(id) && killed.insert(*id))
+				.count();
+			zone.addKilled(count);
+		}
+		
+		self.expired += expired.len();
+		self.killed += killed.len();
+		expired.extend(killed);
+		self.removeIds(&expired);
+	}
+	
+	/// Called with every contact begin, persist and end event at the end of each update, returns the listener id
+	pub fn addContactListener<F: FnMut(&ContactEvent) + Send + 'static>(&mut self, listener: F) -> usize {
+		let id = newId();
+		self.contactListeners.push((id, Box::new(listener)));
+		id
+	}
+	
+	pub fn removeContactListener(&mut self, id: usize) -> bool {
+		let len = self.contactListeners.len();
+		self.contactListeners.retain(|(listenerId, _)| *listenerId != id);
+		len != self.contactListeners.len()
+	}
+	
+	/// Pairs touching at the end of the last update
+	pub fn contactCount(&self) -> usize {
+		self.touching.len()
+	}
+	
+	/// Sleeping pairs skip collision, they're kept touching until one of them wakes
+	fn dispatchContacts(&mut self) {
+		let found = std::mem::take(&mut *self.foundContacts.lock().unwrap());
+		let physicals = &self.physicals;
+		let events = diffContacts(&mut self.touching, found, |contact| {
+			match (physicals.get(&contact.id1), physicals.get(&contact.id2)) {
+				(Some(physical1), Some(physical2)) => physical1.read().unwrap().sleeping() && physical2.read().unwrap().sleeping(),
+				_ => false,
+			}
+		});
+		
+		self.sendContactEvents(&events);
+	}
+	
+	/// Count, log and hand events to every listener
+	fn sendContactEvents(&mut self, events: &[ContactEvent]) {
+		for event in events.iter() {
+			match event {
+				ContactEvent::Begin(_) => self.contactEventCounts[0] += 1,
+				ContactEvent::Persist(_) => self.contactEventCounts[1] += 1,
+				ContactEvent::End(_) => self.contactEventCounts[2] += 1,
+			}
+			if !matches!(event, ContactEvent::Persist(_)) {
+				if self.contactLog.len() == CONTACT_LOG_SIZE {
+					self.contactLog.pop_front();
+				}
+				self.contactLog.push_back(*event);
+			}
+			for (_, listener) in self.contactListeners.iter_mut() {
+				listener(event);
+			}
+		}
+	}
+	
+	pub fn addObstacle(&mut self, obstacle: ObstacleRef) {
+		self.obstacles.push(obstacle);
+		self.rebuildObstacleGrid();
+	}
+	
+	pub fn removeObstacle(&mut self, id: usize) -> bool {
+		let len = self.obstacles.len();
+		self.obstacles.retain(|obstacle| obstacle.id() != id);
+		if len == self.obstacles.len() {
+			return false;
+		}
+		self.rebuildObstacleGrid();
+		true
+	}
+	
+	/// Grid covers the world and every obstacle, even those placed outside of it
+	fn rebuildObstacleGrid(&mut self) {
+		let mut min = -self.worldSize / 2.0;
+		let mut max = self.worldSize / 2.0;
+		for obstacle in self.obstacles.iter() {
+			min = min.min(obstacle.bounds().start());
+			max = max.max(obstacle.bounds().end());
+		}
+		
+		let mut grid = UniformGrid::new(OBSTACLE_CELLS, AABB::new(min, max - min));
+		for obstacle in self.obstacles.iter() {
+			grid.insert(obstacle.clone(), obstacle.bounds());
+		}
+		self.obstacleGrid = Arc::new(grid);
+		self.wakeAll();
+	}
+	
+	/// Box of four corner particles held together by its edges and diagonals, returns the box id
+	pub fn addBox(&mut self, center: Vec3, size: Vec3, cornerSize: f32, color: Vec3) -> usize {
+		let halfSize = size / 2.0;
+		let offsets = [
+			Vec3::new(-halfSize.x, -halfSize.y, 0.0),
+			Vec3::new(halfSize.x, -halfSize.y, 0.0),
+			Vec3::new(halfSize.x, halfSize.y, 0.0),
+			Vec3::new(-halfSize.x, halfSize.y, 0.0),
+		];
+		let corners = offsets.map(|offset| {
+			let mut ball = Ball::new(center + offset, Vec3::splat(cornerSize));
+			ball.color = color;
+			let ball = newPhysicalRef(ball);
+			self.addPhysical(ball.clone());
+			ball
+		});
+		
+		let mut constraints = Vec::with_capacity(6);
+		for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (1, 3)] {
+			let mut constraint = DistanceConstraint::between(&corners[a], &corners[b]);
+			constraint.color = color;
+			constraints.push(constraint.id());
+			self.addConstraint(newConstraintRef(constraint));
+		}
+		
+		let mut constraintBox = ConstraintBox::new(corners.map(|corner| corner.read().unwrap().id()), constraints);
+		constraintBox.color = color;
+		let id = constraintBox.id();
+		self.boxes.push(constraintBox);
+		id
+	}
+	
+	/// Ring of `segments` balls joined by springs with gas pressure inside, returns the pressure constraint's id
+	pub fn addSoftBody(&mut self, center: Vec3, radius: f32, segments: usize, particleSize: f32, color: Vec3) -> usize {
+		let segments = segments.max(3);
+		let ring: Vec<PhysicalRef> = (0..segments).map(|i| {
+			let angle = i as f32 * TAU / segments as f32;
+			let mut ball = Ball::new(center + Vec3::new(angle.cos(), angle.sin(), 0.0) * radius, Vec3::splat(particleSize));
+			ball.color = color;
+			let ball = newPhysicalRef(ball);
+			self.addPhysical(ball.clone());
+			ball
+		}).collect();
+		
+		for i in 0..segments {
+			let mut spring = SpringConstraint::between(&ring[i], &ring[(i + 1) % segments], SOFT_BODY_STIFFNESS, SOFT_BODY_DAMPING);
+			spring.color = color;
+			self.addConstraint(newConstraintRef(spring));
+		}
+		
+		let mut pressure = PressureConstraint::around(&ring, SOFT_BODY_PRESSURE);
+		pressure.color = color;
+		let id = pressure.id();
+		self.addConstraint(newConstraintRef(pressure));
+		id
+	}
+	
+	/// Fluid particles a diameter apart filling `size` around `center`, returns their ids
+	pub fn addFluid(&mut self, center: Vec3, size: Vec3, radius: f32, color: Vec3) -> Vec<usize> {
+		let spacing = radius.max(0.5) * 2.0;
+		let columns = (size.x / spacing).floor().max(1.0) as usize;
+		let rows = (size.y / spacing).floor().max(1.0) as usize;
+		let start = center - Vec3::new(columns as f32 - 1.0, rows as f32 - 1.0, 0.0) * spacing / 2.0;
+		
+		let mut ids = Vec::with_capacity(columns * rows);
+		for y in 0..rows {
+			for x in 0..columns {
+				let mut ball = Ball::new(start + Vec3::new(x as f32, y as f32, 0.0) * spacing, Vec3::splat(spacing));
+				ball.color = color;
+				ball.setFluid(true);
+				ids.push(ball.id());
+				self.addPhysical(newPhysicalRef(ball));
+			}
+		}
+		ids
+	}
+	
+	/// Particles from `start` to `end` linked in a line, returns their ids in order
+	pub fn addRope(&mut self, start: Vec3, end: Vec3, options: &RopeOptions) -> Vec<usize> {
+		let segments = options.segments.max(1);
+		let particles: Vec<PhysicalRef> = (0..=segments).map(|i| {
+			let position = start.lerp(end, i as f32 / segments as f32);
+			let pinned = (i == 0 && options.pinStart) || (i == segments && options.pinEnd);
+			self.addParticle(position, options.particleRadius, pinned, options.color)
+		}).collect();
+		
+		for pair in particles.windows(2) {
+			self.addLink(&pair[0], &pair[1], options.stiffness, options.color);
+		}
+		particles.iter().map(|particle| particle.read().unwrap().id()).collect()
+	}
+	
+	/// Grid of particles spanning `size` around `center`, returns their ids row by row from the top
+	pub fn addCloth(&mut self, center: Vec3, size: Vec3, options: &ClothOptions) -> Vec<usize> {
+		let columns = options.columns.max(2);
+		let rows = options.rows.max(2);
+		let spacing = Vec3::new(size.x / (columns - 1) as f32, size.y / (rows - 1) as f32, 0.0);
+		let topLeft = center + Vec3::new(-size.x / 2.0, size.y / 2.0, 0.0);
+		
+		let mut particles = Vec::with_capacity(columns * rows);
+		for y in 0..rows {
+			for x in 0..columns {
+				let position = topLeft + Vec3::new(x as f32 * spacing.x, -(y as f32) * spacing.y, 0.0);
+				particles.push(self.addParticle(position, options.particleRadius, y == 0 && options.pinTop, options.color));
+			}
+		}
+		
+		let at = |x: usize, y: usize| &particles[x + y * columns];
+		for y in 0..rows {
+			for x in 0..columns {
+				if x + 1 < columns {
+					self.addLink(at(x, y), at(x + 1, y), options.stiffness, options.color);
+				}
+				if y + 1 < rows {
+					self.addLink(at(x, y), at(x, y + 1), options.stiffness, options.color);
+				}
+				if x + 1 < columns && y + 1 < rows {
+					self.addLink(at(x, y), at(x + 1, y + 1), options.shearStiffness, options.color);
+					self.addLink(at(x + 1, y), at(x, y + 1), options.shearStiffness, options.color);
+				}
+				if x + 2 < columns {
+					self.addLink(at(x, y), at(x + 2, y), options.bendStiffness, options.color);
+				}
+				if y + 2 < rows {
+					self.addLink(at(x, y), at(x, y + 2), options.bendStiffness, options.color);
+				}
+			}
+		}
+		particles.iter().map(|particle| particle.read().unwrap().id()).collect()
+	}
+	
+	fn addParticle(&mut self, position: Vec3, radius: f32, pinned: bool, color: Vec3) -> PhysicalRef {
+		let mut ball = Ball::new(position, Vec3::splat(radius * 2.0));
+		ball.color = color;
+		if pinned {
+			ball.pin();
+		}
+		let ball = newPhysicalRef(ball);
+		self.addPhysical(ball.clone());
+		ball
+	}
+	
+	fn addLink(&mut self, physical1: &PhysicalRef, physical2: &PhysicalRef, stiffness: f32, color: Vec3) {
+		if stiffness <= 0.0 {
+			return;
+		}
+		let mut link = DistanceConstraint::between(physical1, physical2);
+		link.stiffness = stiffness.min(1.0);
+		link.color = color;
+		self.addConstraint(newConstraintRef(link));
+	}
+	
+	/// Physicals overlapping an area, from whichever tree the active collision mode keeps
+	fn findInArea(&self, area: &AABB) -> Vec<PhysicalRef> {
+		let overlaps = |physical: &PhysicalRef, _: &AABB| {
+			area.overlaps(&physical.read().unwrap().bounds())
+		};
+		// Trees hold physicals by position, so nodes are searched with room for the largest shape
+		let searchArea = area.grow(Vec3::splat((self.maxBoundingRadius + self.treeDrift) * 2.0));
+		
+		let mut found = Vec::new();
+		if self.flags.get(F_THREAD_MODE) {
+			for chunk in self.chunks.iter() {
+				let chunk = chunk.read().unwrap();
+				if chunk.tree.bounds().overlaps(&searchArea) {
+					found.append(&mut chunk.tree.findInArea(&searchArea, &overlaps));
+				}
+			}
+		} else {
+			found = self.quadTree.findInArea(&searchArea, &overlaps);
+		}
+		found.append(&mut self.sleepingTree.findInArea(&searchArea, &overlaps));
+		found.extend(self.outsideTrees.iter().filter(|physical| overlaps(physical, area)).cloned());
+		
+		// Physicals on a split line end up in both halves
+		found.sort_by_key(|physical| physical.read().unwrap().id());
+		found.dedup_by_key(|physical| physical.read().unwrap().id());
+		found
+	}
+	
+	/// Ids of physicals whose shape contains the point
+	pub fn queryPoint(&self, point: Vec3) -> Vec<usize> {
+		self.findInArea(&AABB::new(point, Vec3::ZERO)).into_iter()
+			.filter_map(|physical| {
+				let physical = physical.read().unwrap();
+				physical.shape().containsPoint(physical.transform(), point).then(|| physical.id())
+			})
+			.collect()
+	}
+	
+	/// Ids of physicals whose shape overlaps the area
+	pub fn queryAabb(&self, area: &AABB) -> Vec<usize> {
+		let transform = Transform {
+			position: area.center(),
+			scale: area.size,
+			..Default::default()
+		};
+		self.queryShape(area, &Shape::Box, &transform)
+	}
+	
+	/// Ids of physicals whose shape overlaps the circle
+	pub fn queryCircle(&self, center: Vec3, radius: f32) -> Vec<usize> {
+		let transform = Transform {
+			position: center,
+			scale: Vec3::splat(radius * 2.0),
+			..Default::default()
+		};
+		self.queryShape(&AABB::centered(center, transform.scale), &Shape::Circle, &transform)
+	}
+	
+	fn queryShape(&self, area: &AABB, shape: &Shape, transform: &Transform) -> Vec<usize> {
+		self.findInArea(area).into_iter()
+			.filter_map(|physical| {
+				let physical = physical.read().unwrap();
+				let contact = Shape::collide(physical.shape(), physical.transform(), shape, transform);
+				contact.map(|_| physical.id())
+			})
+			.collect()
+	}
+	
+	/// Physicals whose shape could reach the segment grown by `radius`
+	fn findOnSegment(&self, start: Vec3, end: Vec3, radius: f32) -> Vec<PhysicalRef> {
+		// Trees hold physicals by position, so nodes are grown by the largest shape too
+		let padding = radius + self.maxBoundingRadius + self.treeDrift;
+		
+		let mut found = Vec::new();
+		if self.flags.get(F_THREAD_MODE) {
+			for chunk in self.chunks.iter() {
+				found.append(&mut chunk.read().unwrap().tree.findOnSegment(start, end, padding));
+			}
+		} else {
+			found = self.quadTree.findOnSegment(start, end, padding);
+		}
+		found.append(&mut self.sleepingTree.findOnSegment(start, end, padding));
+		let area = AABB::new(start.min(end), (end - start).abs()).grow(Vec3::splat(radius * 2.0));
+		found.extend(self.outsideTrees.iter().filter(|physical| area.overlaps(&physical.read().unwrap().bounds())).cloned());
+		
+		found.sort_by_key(|physical| physical.read().unwrap().id());
+		found.dedup_by_key(|physical| physical.read().unwrap().id());
+		found
+	}
+	
+	pub fn raycast(&self, origin: Vec3, direction: Vec3, maxDistance: f32) -> Option<RayHit> {
+		self.circleCast(origin, direction, 0.0, maxDistance)
+	}
+	
+	pub fn segmentCast(&self, start: Vec3, end: Vec3) -> Option<RayHit> {
+		self.raycast(start, end - start, start.distance(end))
+	}
+	
+	/// Sweep a circle along `direction`, physicals it starts out overlapping are hit at zero unless it's moving out of them
+	pub fn circleCast(&self, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32) -> Option<RayHit> {
+		self.sweep(origin, direction, radius, maxDistance, &|_| true)
+	}
+	
+	fn sweep(&self, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32, accept: &dyn Fn(&dyn Physical) -> bool) -> Option<RayHit> {
+		let direction = direction.normalize_or_zero();
+		if direction == Vec3::ZERO || maxDistance < 0.0 {
+			return None;
+		}
+		let radius = radius.max(0.0);
+		
+		let mut closest: Option<RayHit> = None;
+		for physical in self.findOnSegment(origin, origin + direction * maxDistance, radius) {
+			let physical = physical.read().unwrap();
+			if !accept(&*physical) {
+				continue;
+			}
+			let Some((distance, normal)) = physical.shape().cast(physical.transform(), origin, direction, radius, maxDistance) else {
+				continue;
+			};
+			if closest.is_some_and(|hit| hit.distance <= distance) {
+				continue;
+			}
+			closest = Some(RayHit {
+				id: physical.id(),
+				point: origin + direction * distance - normal * radius,
+				normal,
+				distance,
+			});
+		}
+		closest
+	}
+	
+	/// Closest obstacle or container wall a circle swept from `origin` touches, with the normal facing back along the sweep
+	fn castBoundary(&self, origin: Vec3, direction: Vec3, radius: f32, maxDistance: f32) -> Option<(f32, Vec3)> {
+		let end = origin + direction * maxDistance;
+		let area = AABB::new(origin.min(end), (end - origin).abs()).grow(Vec3::splat(radius * 2.0));
+		let mut closest = self.container.cast(origin, direction, radius, maxDistance, self.worldSize);
+		for obstacle in self.obstacleGrid.findInArea(&area, &|obstacle, area| obstacle.bounds().overlaps(area)) {
+			if let Some((distance, normal)) = obstacle.cast(origin, direction, radius, maxDistance)
+				&& closest.is_none_or(|(closest, _)| distance < closest) {
+				closest = Some((distance, normal));
+			}
+		}
+		closest
+	}
+	
+	/// Sweep balls that moved further than their radius this sub step back from their last position,
+	/// stopping them at the first physical, obstacle or container wall they hit and bouncing them off it
+	fn collideContinuous(&mut self, dt: f32) {
+		let mut fast = Vec::new();
+		for (id, physical) in self.physicals.iter() {
+			let physical = physical.read().unwrap();
+			let displacement = (physical.transform().position - physical.lastTransform().position).length();
+			if physical.inverseMass() > 0.0 && physical.shape().isCircle() && displacement > physical.shape().radius(physical.transform()) {
+				fast.push(*id);
+			}
+		}
+		
+		for id in fast {
+			let physical = self.physicals[&id].clone();
+			let (start, displacement, radius) = {
+				let physical = physical.read().unwrap();
+				let start = physical.lastTransform().position;
+				(start, physical.transform().position - start, physical.shape().radius(physical.transform()))
+			};
+			let distance = displacement.length();
+			let direction = displacement / distance;
+			
+			let accept = |other: &dyn Physical| other.id() != id && Self::interacts(&*physical.read().unwrap(), other);
+			let hit = self.sweep(start, direction, radius, distance, &accept);
+			let boundaryHit = self.castBoundary(start, direction, radius, hit.map_or(distance, |hit| hit.distance));
+			if let Some((boundaryDistance, normal)) = boundaryHit {
+				let mut physical = physical.write().unwrap();
+				let mut velocity = displacement / dt;
+				let inward = velocity.dot(normal);
+				if inward < 0.0 {
+					velocity -= normal * inward * (1.0 + physical.elasticity());
+				}
+				physical.transformMut().position = start + direction * boundaryDistance;
+				physical.setVelocity(velocity, dt);
+			} else if let Some(hit) = hit {
+				let other = self.physicals[&hit.id].clone();
+				let mut physical = physical.write().unwrap();
+				let mut other = other.write().unwrap();
+				if other.sleeping() {
+					other.setSleeping(false);
+				}
+				
+				let mut velocity1 = displacement / dt;
+				let mut velocity2 = other.getVelocity(dt);
+				physical.transformMut().position = start + direction * hit.distance;
+				
+				let weight1 = physical.inverseMass();
+				let weight2 = other.inverseMass();
+				let approach = (velocity1 - velocity2).dot(hit.normal);
+				if approach < 0.0 {
+					let restitution = (physical.elasticity() + other.elasticity()) / 2.0;
+					let impulse = -(1.0 + restitution) * approach / (weight1 + weight2);
+					velocity1 += hit.normal * impulse * weight1;
+					velocity2 -= hit.normal * impulse * weight2;
+				}
+				physical.setVelocity(velocity1, dt);
+				other.setVelocity(velocity2, dt);
+			}
+			Self::collideWithBoundary(dt, physical, &self.container, &self.obstacleGrid, self.worldSize, true);
+		}
+	}
+	
+	fn collideBoxes(&self) {
+		for (i, box1) in self.boxes.iter().enumerate() {
+			for box2 in self.boxes[i + 1..].iter() {
+				box1.collideBox(box2, &self.physicals);
+			}
+		}
+		
+		for constraintBox in self.boxes.iter() {
+			let Some(polygon) = constraintBox.polygon(&self.physicals) else {
+				continue;
+			};
+			
+			for physical in self.findInArea(&polygonBounds(&polygon)) {
+				let id = physical.read().unwrap().id();
+				// Corners of other boxes were handled box against box above
+				if self.boxes.iter().any(|other| other.hasCorner(id)) {
+					continue;
+				}
+				constraintBox.collidePhysical(&self.physicals, &physical);
+			}
+		}
+	}
+	
+	fn solveConstraints(&self, dt: f32) {
+		for constraint in self.constraints.iter() {
+			constraint.write().unwrap().solve(&self.physicals, dt);
+		}
+	}
+	
+	/// Density of every fluid particle from its neighbours within the smoothing radius,
+	/// then pressure and viscosity accelerations from those densities
+	fn solveFluid(&self, dt: f32) {
+		let particles: Vec<PhysicalRef> = self.physicals.values()
+			.filter(|physical| physical.read().unwrap().fluid())
+			.cloned()
+			.collect();
+		if particles.is_empty() {
+			return;
+		}
+		let settings = self.fluid;
+		
+		// Position, velocity and mass
+		let states: Vec<(Vec3, Vec3, f32)> = particles.iter().map(|physical| {
+			let physical = physical.read().unwrap();
+			(physical.transform().position, physical.getVelocity(dt), physical.mass())
+		}).collect();
+		let indices: HashMap<usize, usize> = particles.iter().enumerate()
+			.map(|(i, physical)| (physical.read().unwrap().id(), i))
+			.collect();
+		let searchSize = Vec3::splat(settings.smoothingRadius * 2.0);
+		let neighbours: Vec<Vec<usize>> = states.iter().map(|(position, _, _)| {
+			self.findInArea(&AABB::centered(*position, searchSize)).into_iter()
+				.filter_map(|physical| indices.get(&physical.read().unwrap().id()).copied())
+				.filter(|j| states[*j].0.distance(*position) < settings.smoothingRadius)
+				.collect()
+		}).collect();
+		
+		// Every particle counts towards its own density, so only massless ones end up at zero
+		let densities: Vec<f32> = states.iter().zip(neighbours.iter()).map(|((position, _, _), found)| {
+			found.iter().map(|j| states[*j].2 * settings.density(states[*j].0.distance(*position))).sum()
+		}).collect();
+		let pressures: Vec<f32> = densities.iter().map(|density| settings.pressure(*density)).collect();
+		
+		for (i, physical) in particles.iter().enumerate() {
+			if densities[i] <= 0.0 {
+				continue;
+			}
+			let (position, velocity, _) = states[i];
+			let mut acceleration = Vec3::ZERO;
+			for j in neighbours[i].iter().copied() {
+				if i == j || densities[j] <= 0.0 {
+					continue;
+				}
+				let (otherPosition, otherVelocity, mass) = states[j];
+				let offset = position - otherPosition;
+				acceleration += settings.pressureGradient(offset) * mass * (pressures[i] + pressures[j]) / (2.0 * densities[j]);
+				acceleration += (otherVelocity - velocity) * mass / densities[j] * settings.viscosity * settings.viscosityLaplacian(offset.length());
+			}
+			physical.write().unwrap().accelerate(acceleration / densities[i]);
+		}
+	}
+	
+	/// Blocking in deterministic mode, otherwise a contended physical is skipped
+	fn lockPhysical(physical: &PhysicalRef, deterministic: bool) -> Option<RwLockWriteGuard<'_, dyn Physical + 'static>> {
+		if deterministic {
+			physical.write().ok()
+		} else {
+			physical.try_write().ok()
+		}
+	}
+	
+	// todo: try collision checks with rays
+	fn collideWithPhysical(physical1: PhysicalRef, physical2: PhysicalRef, deterministic: bool) -> Option<PhysicalContact> {
+		if let Some(mut physical1) = Self::lockPhysical(&physical1, deterministic) {
+			if let Some(mut physical2) = Self::lockPhysical(&physical2, deterministic) {
+				if (physical1.sleeping() && physical2.sleeping()) || !Self::interacts(&*physical1, &*physical2) {
+					return None;
+				}
+				let contact = Shape::collide(physical1.shape(), physical1.transform(), physical2.shape(), physical2.transform());
+				if let Some(contact) = contact {
+					// Resting bodies lie on sleeping ones like on a fixed body, moving ones wake them
+					if physical1.sleeping() && Self::restless(&*physical2) {
+						physical1.setSleeping(false);
+					}
+					if physical2.sleeping() && Self::restless(&*physical1) {
+						physical2.setSleeping(false);
+					}
+					
+					let inverseMass1 = physical1.inverseMass();
+					let inverseMass2 = physical2.inverseMass();
+					let inverseMassSum = inverseMass1 + inverseMass2;
+					if inverseMassSum <= 0.0 {
+						return None;
+					}
+					
+					let massRatio1 = inverseMass1 / inverseMassSum;
+					let massRatio2 = inverseMass2 / inverseMassSum;
+					let force = ((physical1.elasticity() + physical2.elasticity()) / 2.0) / 2.0 * contact.depth;
+					
+					physical1.transformMut().position += contact.normal * massRatio1 * force;
+					physical2.transformMut().position -= contact.normal * massRatio2 * force;
+					return Some(PhysicalContact {
+						id1: physical1.id(),
+						id2: physical2.id(),
+						point: contact.point,
+						normal: contact.normal,
+						depth: contact.depth,
+					});
+				}
+			}
+		}
+		None
+	}
+	
+	/// Layers and masks let the pair collide and they aren't both fluid, fluid pairs only feel pressure
+	fn interacts(physical1: &dyn Physical, physical2: &dyn Physical) -> bool {
+		physical1.collidesWith(physical2) && !(physical1.fluid() && physical2.fluid())
+	}
+	
+	/// Moved faster than `sleepSpeed` last update
+	pub(crate) fn restless(physical: &dyn Physical) -> bool {
+		!physical.fixed() && !physical.sleeping() && physical.restTime() <= 0.0
+	}
+	
+	/// Obstacles first so the container has the final say
+	fn collideWithBoundary(_dt: f32, physical: PhysicalRef, container: &Container, obstacles: &UniformGrid<ObstacleRef>, worldSize: Vec3, deterministic: bool) {
+		if let Some(mut physical) = Self::lockPhysical(&physical, deterministic) {
+			if physical.sleeping() {
+				return;
+			}
+			let mut found = obstacles.findInArea(&physical.bounds(), &|obstacle, area| obstacle.bounds().overlaps(area));
+			found.sort_by_key(|obstacle| obstacle.id());
+			found.dedup_by_key(|obstacle| obstacle.id());
+			for obstacle in found.iter() {
+				obstacle.collide(&mut *physical);
+			}
+			container.collide(&mut *physical, worldSize);
+		}
+	}
+			
+	/// Gravity, force fields and verlet integration for a single physical
+	fn integratePhysical(physical: &mut dyn Physical, dt: f32, gravity: Vec3, forceFields: &[ForceFieldRef]) {
+		if physical.sleeping() {
+			return;
+		}
+		physical.accelerate(gravity);
+		for field in forceFields.iter() {
+			field.read().unwrap().apply(physical, dt);
+		}
+		physical.update(dt);
+	}
+	
+	fn insertionSort<T, F>(vec: &mut Vec<T>, mut compare: F)
+	where
+		F: FnMut(&T, &T) -> bool,
+	{
+		// Taken with 800 physicals and 1,-400 gravity
+		// ~10.930447ms first sort
+		// ~0.15062812ms second sort and onwards
+		let n = 1..vec.len();
+		for i in n {
+			let mut j = i;
+			while j > 0 && compare(&vec[j], &vec[j - 1]) {
+				vec.swap(j, j - 1);
+				j -= 1;
+			}
+		}
+	
+		// Taken with 800 physicals and 1,-400 gravity
+		// ~10.008063ms first
+		// ~2.7993727ms second-fifth
+		// lowers afterwards
+		// for i in 1..vec.len() {
+		// 	for j in (0..i).rev() {// j=i-1; j >= 0; j--
+		// 		if compare(&vec[j], &vec[j + 1]) {
+		// 			break;
+		// 		}
+		// 		vec.swap(j, j + 1);
+		// 	}
+		// }
+	}
+	
+	fn sweepEdges(edges: &Vec<Edge>) -> Vec<(usize, usize)> {
+		let mut pairs = Vec::<(usize, usize)>::new();
+	
+		let mut touching = Vec::<usize>::new();
+		for edge in edges.iter() {
+			let edgeId = edge.id;
+			if edge.isMinimum {
+				for other in touching.iter() {
+					let other = *other;
+					pairs.push((other.min(edgeId), edgeId.max(other)));
+				}
+				touching.push(edgeId);
+			} else {
+				if let Some(index) = touching.iter().position(|x| *x == edgeId) {
+					touching.remove(index);
+				}
+				// touching.retain(|x| *x != edgeId);
+			}
+		}
+	
+		pairs
+	}
+	
+	fn calcVarianceForEdges(edges: &Vec<Edge>) -> f32 {
+		let mut sum = 0.0;
+		for edge in edges.iter() {
+			sum += edge.coord;
+		}
+		let mean = sum / edges.len() as f32;
+	
+		let mut squaredDiffSum = 0.0;
+		for edge in edges.iter() {
+			let diff = edge.coord - mean;
+			squaredDiffSum += diff * diff;
+		}
+	
+		squaredDiffSum / edges.len() as f32
+	}
+	
+	fn calcEdgeCoords(&mut self) {
+		let now = Instant::now();
+	
+		for i in 0..self.edgesX.len() {
+			let edgeX = &mut self.edgesX[i];
+			let bounds = {
+				let borrow = self.physicals[&edgeX.id].read().unwrap();
+				borrow.shape().bounds(borrow.transform())
+			};
+			if edgeX.isMinimum {
+				edgeX.coord = bounds.start().x;
+			} else {
+				edgeX.coord = bounds.end().x;
+			}
+	
+			let edgeY = &mut self.edgesY[i];
+			let bounds = {
+				let borrow = self.physicals[&edgeY.id].read().unwrap();
+				borrow.shape().bounds(borrow.transform())
+			};
+			if edgeY.isMinimum {
+				edgeY.coord = bounds.start().y;
+			} else {
+				edgeY.coord = bounds.end().y;
+			}
+		}
+	
+		let end = now.elapsed().as_secs_f32() * 1000.0;
+		self.calcEdgeCoordsAccum += end;
+	}
+	
+	fn broadPhaseCollisionCheck(&mut self) {
+		let now = Instant::now();
+	
+		Self::insertionSort(&mut self.edgesX, |a, b| a.coord < b.coord);
+		Self::insertionSort(&mut self.edgesY, |a, b| a.coord < b.coord);
+	
+		let end = now.elapsed().as_secs_f32() * 1000.0;
+		self.sortTimeAccum += end;
+	
+		let now = Instant::now();
+	
+		// let mut touching: Vec<usize> = Vec::new();
+		// for edge in self.edgesX.iter() {
+		// 	let edgeId = edge.physical.borrow().id();
+		// 	if edge.isMinimum {
+		// 		for other in touching.iter() {
+		// 			self.collideWithPhysical(self.physicals.get(&other).unwrap().clone(), edge.physical.clone());
+		// 		}
+		// 		// self.collideWithBoundary(dt, edge.physical.clone());
+		// 		touching.push(edgeId);
+		// 	} else {
+		// 		// if let Some(index) = touching.iter().position(|x| x.borrow().transform().position == edge.physical.borrow().transform().position) {
+		// 		// 	touching.remove(index);
+		// 		// }
+		// 		touching.retain(|x| *x != edgeId);
+		// 	}
+		// }
+	
+		let pairs = {
+			let varianceX = Self::calcVarianceForEdges(&self.edgesX);
+			let varianceY = Self::calcVarianceForEdges(&self.edgesY);
+			if varianceX > varianceY {
+				self.sweepAxis = "X".to_string();
+				Self::sweepEdges(&self.edgesX)
+			} else {
+				self.sweepAxis = "Y".to_string();
+				Self::sweepEdges(&self.edgesY)
+			}
+		};
+		self.collisionChecks = pairs.len();
+	
+		// let pairsX = Self::sweepEdges(&self.edgesX);//.into_iter().collect::<HashSet<_>>();
+		// let pairsY = self.sweepEdges(&self.edgesY);//.into_iter().collect::<HashSet<_>>();
+		// let pairs = pairsY.into_iter().filter(|x| pairsX.contains(x)).collect::<Vec<_>>();
+		// let pairs = pairsX.intersection(&pairsY).collect::<HashSet<_>>();
+	
+		let deterministic = self.isDeterministic();
+		let mut contacts = Vec::new();
+		for (a, b) in pairs.into_iter() {
+			let physical1 = self.physicals[&a].clone();
+			let physical2 = self.physicals[&b].clone();
+			contacts.extend(Self::collideWithPhysical(physical1, physical2, deterministic));
+		}
+		self.foundContacts.lock().unwrap().append(&mut contacts);
+	
+		let end = now.elapsed().as_secs_f32() * 1000.0;
+		self.sweepTimeAccum += end;
+	}
+	
+	fn updatePhysicals(&self, dt: f32) {
+		let deterministic = self.isDeterministic();
+		for (_, physical) in self.physicals.iter() {
+			let physical = physical.clone();
+			Self::integratePhysical(&mut *physical.write().unwrap(), dt, self.gravity, &self.forceFields);
+			Self::collideWithBoundary(dt, physical, &self.container, &self.obstacleGrid, self.worldSize, deterministic);
+		}
+	}
+	
+	/// Rebuild the sleeping tree if anything fell asleep or woke up since the last call,
+	/// returns the physicals the per update trees are built from
+	fn partitionSleeping(&mut self) -> Vec<PhysicalRef> {
+		let mut awake = Vec::with_capacity(self.physicals.len());
+		let mut sleeping = Vec::new();
+		for (id, physical) in self.physicals.iter() {
+			if physical.read().unwrap().sleeping() {
+				sleeping.push((*id, physical.clone()));
+			} else {
+				awake.push(physical.clone());
+			}
+		}
+		
+		if sleeping.len() != self.sleepingIds.len() || sleeping.iter().zip(self.sleepingIds.iter()).any(|((id, _), sleepingId)| id != sleepingId) {
+			let mut tree = BSPGrid::new(GRID_CAPACITY, AABB::centered(Vec3::ZERO, self.worldSize));
+			self.sleepingOutside.clear();
+			for (_, physical) in sleeping.iter() {
+				let inserted = tree.insert(physical.clone(), &|physical, bounds| {
+					bounds.containsPoint(physical.read().unwrap().transform().position)
+				});
+				if !inserted {
+					self.sleepingOutside.push(physical.clone());
+				}
+			}
+			self.sleepingTree = Arc::new(tree);
+			self.sleepingIds = sleeping.into_iter().map(|(id, _)| id).collect();
+		}
+		awake.extend(self.sleepingOutside.iter().cloned());
+		awake
+	}
+	
+	/// Returns the physicals in the tree, everything but what sleeps inside the world
+	fn populateQuadTree(&mut self) -> Vec<PhysicalRef> {
+		// 17+ fps
+		// ~3.5ms
+		let awake = self.partitionSleeping();
+		self.quadTree.clear();
+		self.treeDrift = 0.0;
+		self.outsideTrees.clear();
+		for physical in awake.iter() {
+			let inserted = self.quadTree.insert(physical.clone(), &|physical, bounds| {
+				bounds.containsPoint(physical.read().unwrap().transform().position)
+			});
+			if !inserted {
+				self.outsideTrees.push(physical.clone());
+			}
+		}
+		awake
+	}
+	
+	/// Add the furthest anything moved this sub step, so queries keep finding physicals that left their tree node
+	fn trackTreeDrift(&mut self) {
+		let maxDisplacement = self.physicals.values()
+			.map(|physical| {
+				let physical = physical.read().unwrap();
+				physical.transform().position.distance(physical.lastTransform().position)
+			})
+			.fold(0.0, f32::max);
+		self.treeDrift += maxDisplacement;
+	}
+	
+	fn subStep(&mut self, dt: f32) {
+		let now = Instant::now();
+	
+		if self.flags.get(F_COLLISION_MODE) {
+			let awake = self.populateQuadTree();
+			let deterministic = self.isDeterministic();
+			let mut contacts = Vec::new();
+	
+			// ~5ms
 			// ~50ms (full step)
-			for (id, physical) in self.physicals.iter() {
-				let found = {
-					let area = physical.read().unwrap().bounds();
-					self.quadTree.findInArea(&area, &|physical, bounds| {
-						bounds.overlaps(&physical.read().unwrap().bounds())
-					})
+			for physical in awake.iter() {
+				let (id, found) = {
+					let physical = physical.read().unwrap();
+					let area = physical.bounds();
+					let overlaps = |physical: &PhysicalRef, bounds: &AABB| bounds.overlaps(&physical.read().unwrap().bounds());
+					let mut found = self.quadTree.findInArea(&area, &overlaps);
+					found.append(&mut self.sleepingTree.findInArea(&area, &overlaps));
+					(physical.id(), found)
 				};
 				for physical2 in found.into_iter() {
-					if *id == physical2.read().unwrap().id() {
+					if id == physical2.read().unwrap().id() {
 						continue;
 					}
-					Self::collideWithPhysical(physical.clone(), physical2.clone());
+					contacts.extend(Self::collideWithPhysical(physical.clone(), physical2.clone(), deterministic));
 				}
 			}
+			self.foundContacts.lock().unwrap().append(&mut contacts);
 		} else {
 			// 5-18 fps
 			// ~27ms
@@ -546,23 +1727,29 @@ impl Solver {
 			self.calcEdgeCoords();
 			self.broadPhaseCollisionCheck();
 		}
+		self.collideBoxes();
+		self.solveConstraints(dt);
+		self.solveFluid(dt);
 		self.updatePhysicals(dt);
+		if self.isCcd() {
+			self.collideContinuous(dt);
+		}
+		self.trackTreeDrift();
 	
 		let end = now.elapsed().as_secs_f32() * 1000.0;
 		self.subStepTimeAccum += end;
 	}
 	
-	fn collideBroadPhaseChunk(dt: f32, chunk: ChunkRef, worldSize: Vec3) {
+	fn collideBroadPhaseChunk(dt: f32, chunk: ChunkRef, container: &Container, obstacles: &UniformGrid<ObstacleRef>, worldSize: Vec3, deterministic: bool, foundContacts: &Mutex<Vec<PhysicalContact>>) {
 		if let Ok(chunk) = chunk.try_read() {
+			let mut contacts = Vec::new();
 			// let now = Instant::now();
+			// Owned since the last rebuild, including physicals that have moved out of the chunk since
 			for physical1 in chunk.physicals.iter() {
-				let (id1, bounds, pos) = {
+				let (id1, bounds) = {
 					let read = physical1.read().unwrap();
-					(read.id(), read.bounds(), read.transform().position)
+					(read.id(), read.bounds())
 				};
-				if !chunk.tree.bounds().containsPoint(pos) {
-					continue;
-				}
 				
 				let mut found = chunk.tree.findInArea(&bounds, &|physical, bounds| {
 					bounds.overlaps(&physical.read().unwrap().bounds())
@@ -580,34 +1767,107 @@ impl Solver {
 						found.append(&mut extra);
 					}
 				}
+				found.append(&mut chunk.sleeping.findInArea(&bounds, &|physical, bounds| {
+					bounds.overlaps(&physical.read().unwrap().bounds())
+				}));
 				
 				for physical2 in found.into_iter() {
 					let id2 = { physical2.read().unwrap().id() };
 					if id1 == id2 {
 						continue;
 					}
-					Self::collideWithPhysical(physical1.clone(), physical2.clone());
+					contacts.extend(Self::collideWithPhysical(physical1.clone(), physical2.clone(), deterministic));
 				}
 				
-				Self::collideWithBoundary(dt, physical1.clone(), worldSize); // todo: fix physicals 'outside' world
+				Self::collideWithBoundary(dt, physical1.clone(), container, obstacles, worldSize, deterministic);
+			}
+			foundContacts.lock().unwrap().append(&mut contacts);
+			// let end = now.elapsed().as_secs_f32() * 1000.0;
+			// info!("Chunk collision took {}ms", end);
+		}
+	}
+	
+	fn updatePhysicalsChunk(dt: f32, chunk: ChunkRef, gravity: Vec3, forceFields: &[ForceFieldRef], deterministic: bool) {
+		if let Ok(chunk) = chunk.try_read() {
+			for physical in chunk.physicals.iter() {
+				if let Some(mut physical) = Self::lockPhysical(physical, deterministic) {
+					Self::integratePhysical(&mut *physical, dt, gravity, forceFields);
+				}
+			}
+		}
+	}
+	
+	fn executeChunkStep(&self, x: usize, y: usize, dt: f32) {
+		let chunk = self.chunks[x + y * CHUNK_COUNT].clone();
+		let gravity = self.gravity;
+		let forceFields = self.forceFields.clone();
+		let container = self.container.clone();
+		let obstacles = self.obstacleGrid.clone();
+		let worldSize = self.worldSize;
+		let deterministic = self.isDeterministic();
+		let foundContacts = self.foundContacts.clone();
+		self.threadPool.execute(move |_| {
+			let now = Instant::now();
+			
+			Self::collideBroadPhaseChunk(dt, chunk.clone(), &container, &obstacles, worldSize, deterministic, &foundContacts);
+			Self::updatePhysicalsChunk(dt, chunk.clone(), gravity, &forceFields, deterministic);
+			
+			let end = now.elapsed().as_micros();
+			U64_ATOMIC_BUFFER.fetch_add(end as u64, Ordering::Relaxed);
+			// info!("{}", end as f32 / 1000.0);
+		});
+	}
+	
+	/// Bodies linked by constraints form an island, which only falls asleep once all of them have rested
+	/// for `sleepTime` and wakes as a whole when any of them is woken
+	/// Fluid particles never sleep, their neighbours' pressure only holds them up while they keep pushing back
+	fn updateSleeping(&self, dt: f32, subStepDt: f32) {
+		fn find(parents: &mut HashMap<usize, usize>, id: usize) -> usize {
+			let parent = *parents.get(&id).unwrap_or(&id);
+			if parent == id {
+				return id;
+			}
+			let root = find(parents, parent);
+			parents.insert(id, root);
+			root
+		}
+		
+		let mut parents = HashMap::new();
+		for constraint in self.constraints.iter() {
+			for (id1, id2) in constraint.read().unwrap().links() {
+				let (root1, root2) = (find(&mut parents, id1), find(&mut parents, id2));
+				if root1 != root2 {
+					parents.insert(root1, root2);
+				}
+			}
+		}
+		
+		// Island root -> (rested, any awake)
+		let mut islands: HashMap<usize, (bool, bool)> = HashMap::new();
+		for (id, physical) in self.physicals.iter() {
+			let mut physical = physical.write().unwrap();
+			if physical.fixed() || physical.fluid() {
+				continue;
+			}
+			if !physical.sleeping() {
+				let restTime = if physical.getVelocity(subStepDt).length() < self.sleepSpeed { physical.restTime() + dt } else { 0.0 };
+				physical.setRestTime(restTime);
 			}
-			// let end = now.elapsed().as_secs_f32() * 1000.0;
-			// info!("Chunk collision took {}ms", end);
+			let island = islands.entry(find(&mut parents, *id)).or_insert((true, false));
+			island.0 &= physical.sleeping() || physical.restTime() >= self.sleepTime;
+			island.1 |= !physical.sleeping();
 		}
-	}
-	
-	fn updatePhysicalsChunk(dt: f32, chunk: ChunkRef, gravity: Vec3) {
-		if let Ok(chunk) = chunk.try_read() {
-			for physical in chunk.physicals.iter() {
-				if let Ok(mut physical) = physical.try_write() {
-					if !chunk.tree.bounds().containsPoint(physical.transform().position) {
-						continue;
-					}
-					physical.accelerate(gravity);
-					// let gravity = (Vec3::ZERO - physical.transform().position).normalize_or_zero() * gravity.length();
-					// physical.accelerate(gravity);
-					physical.update(dt);
-				}
+		
+		for (id, physical) in self.physicals.iter() {
+			let mut physical = physical.write().unwrap();
+			if physical.fixed() || physical.fluid() {
+				continue;
+			}
+			let (rested, awake) = islands[&find(&mut parents, *id)];
+			if rested && !physical.sleeping() {
+				physical.setSleeping(true);
+			} else if !rested && awake && physical.sleeping() {
+				physical.setSleeping(false);
 			}
 		}
 	}
@@ -623,26 +1883,37 @@ impl Solver {
 			let subSteps = self.subSteps;
 			let subStepDt = dt / subSteps as f32;
 			
+			if self.gravity != self.restGravity {
+				self.restGravity = self.gravity;
+				self.wakeAll();
+			}
+			self.contactEventCounts = [0; 3];
+			self.updateEmitters(dt, subStepDt);
+			
 			if self.flags.get(F_THREAD_MODE) {
 				// 30+ fps
 				// ~10-20ms (full step)
 				
-				let gravity = self.gravity;
-				let worldSize = self.worldSize;
-				
+				let awake = Arc::new(self.partitionSleeping());
 				U64_ATOMIC_BUFFER.store(0, Ordering::Relaxed);
-				for x in 0..self.threadPool.getTotal() {
-					for y in 0..self.threadPool.getTotal() {
-						let chunk = self.chunks[x + y * self.threadPool.getTotal()].clone();
-						let physicals = self.physicals.clone();
+				for x in 0..CHUNK_COUNT {
+					for y in 0..CHUNK_COUNT {
+						let chunk = self.chunks[x + y * CHUNK_COUNT].clone();
+						let physicals = awake.clone();
+						let sleeping = self.sleepingTree.clone();
+						let worldSize = self.worldSize;
 						self.threadPool.execute(move |_| {
 							let now = Instant::now();
 							
 							if let Ok(mut chunk) = chunk.try_write() {
 								chunk.tree.clear();
 								chunk.physicals.clear();
+								chunk.sleeping = sleeping;
 								
-								for (_, physical) in physicals.iter() {
+								for physical in physicals.iter() {
+									if !chunk.owns(physical.read().unwrap().transform().position, worldSize) {
+										continue;
+									}
 									if chunk.tree.insert(physical.clone(), &|physical, bounds| {
 										bounds.containsPoint(physical.read().unwrap().transform().position)
 										// bounds.overlaps(&physical.read().unwrap().bounds())
@@ -659,31 +1930,55 @@ impl Solver {
 					}
 				}
 				self.threadPool.waitForCompletion();
-				self.chunkBuildTime = (U64_ATOMIC_BUFFER.load(Ordering::Relaxed) / (self.threadPool.getTotal() * self.threadPool.getTotal()) as u64) as f32 / 1000.0;
+				self.treeDrift = 0.0;
+				self.chunkBuildTime = (U64_ATOMIC_BUFFER.load(Ordering::Relaxed) / (CHUNK_COUNT * CHUNK_COUNT) as u64) as f32 / 1000.0;
+				
+				// Physicals outside the world belong to no chunk, they're still integrated but only collide with the container
+				let mut owned = HashSet::new();
+				for chunk in self.chunks.iter() {
+					owned.extend(chunk.read().unwrap().physicals.iter().map(|physical| physical.read().unwrap().id()));
+				}
+				let orphans: Vec<PhysicalRef> = awake.iter()
+					.filter(|physical| !owned.contains(&physical.read().unwrap().id()))
+					.cloned().collect();
+				self.outsideTrees = orphans.clone();
 				
 				U64_ATOMIC_BUFFER.store(0, Ordering::Relaxed);
-				for x in 0..self.threadPool.getTotal() {
-					for y in 0..self.threadPool.getTotal() {
-						// todo: try different stagger method for better coverage
-						let x = (x + y) % self.threadPool.getTotal(); // Stagger x so no neighboring threads update simultaneously
-						let chunk = self.chunks[x + y * self.threadPool.getTotal()].clone();
-						
-						self.threadPool.execute(move |_| {
-							for _ in 0..subSteps {
-								let now = Instant::now();
-								
-								Self::collideBroadPhaseChunk(subStepDt, chunk.clone(), worldSize);
-								Self::updatePhysicalsChunk(subStepDt, chunk.clone(), gravity);
-								
-								let end = now.elapsed().as_micros();
-								U64_ATOMIC_BUFFER.fetch_add(end as u64, Ordering::Relaxed);
-								// info!("{}", end as f32 / 1000.0);
+				for _ in 0..subSteps {
+					if self.isDeterministic() {
+						// Chunks three apart share no neighbours, so every phase touches its own physicals and run order can't matter
+						for phase in 0..9 {
+							for y in (phase / 3..CHUNK_COUNT).step_by(3) {
+								for x in (phase % 3..CHUNK_COUNT).step_by(3) {
+									self.executeChunkStep(x, y, subStepDt);
+								}
 							}
-						});
+							self.threadPool.waitForCompletion();
+						}
+					} else {
+						for x in 0..CHUNK_COUNT {
+							for y in 0..CHUNK_COUNT {
+								// todo: try different stagger method for better coverage
+								let x = (x + y) % CHUNK_COUNT; // Stagger x so no neighboring threads update simultaneously
+								self.executeChunkStep(x, y, subStepDt);
+							}
+						}
+						self.threadPool.waitForCompletion();
 					}
+					for physical in orphans.iter() {
+						Self::integratePhysical(&mut *physical.write().unwrap(), subStepDt, self.gravity, &self.forceFields);
+						Self::collideWithBoundary(subStepDt, physical.clone(), &self.container, &self.obstacleGrid, self.worldSize, true);
+					}
+					if self.isCcd() {
+						self.collideContinuous(subStepDt);
+					}
+					// Constraints span chunks, so they're solved between sub steps once every chunk is done
+					self.collideBoxes();
+					self.solveConstraints(subStepDt);
+					self.solveFluid(subStepDt);
+					self.trackTreeDrift();
 				}
-				self.threadPool.waitForCompletion();
-				self.subStepTime = (U64_ATOMIC_BUFFER.load(Ordering::Relaxed) / (self.threadPool.getTotal() * self.threadPool.getTotal() * self.subSteps as usize) as u64) as f32 / 1000.0;
+				self.subStepTime = (U64_ATOMIC_BUFFER.load(Ordering::Relaxed) / (CHUNK_COUNT * CHUNK_COUNT * self.subSteps as usize) as u64) as f32 / 1000.0;
 			} else {
 				self.populateQuadTree();
 				for _ in 0..subSteps {
@@ -701,6 +1996,12 @@ impl Solver {
 				self.subStepTime = self.subStepTimeAccum * timeRecip;
 				self.subStepTimeAccum = 0.0;
 			}
+			if self.isSleepMode() {
+				self.updateSleeping(dt, subStepDt);
+			}
+			self.removeExpired(dt);
+			self.updateSensors();
+			self.dispatchContacts();
 
 			let end = now.elapsed().as_secs_f32() * 1000.0;
 			self.stepTime = end;
@@ -710,6 +2011,7 @@ impl Solver {
 		}
 	}
 	
+	#[cfg(feature = "graphics")]
 	pub fn gui(&mut self, ui: &mut Ui, dt: f32) {
 		ui.window("Verlet Solver")
 			.flags(WindowFlags::ALWAYS_AUTO_RESIZE)
@@ -718,16 +2020,341 @@ impl Solver {
 				ui.separator();
 				
 				ui.text(format!("Physicals: {}", self.physicals.len()));
+				ui.text(format!("Constraints: {}", self.constraints.len()));
+				ui.text(format!("Boxes: {}", self.boxes.len()));
+				ui.text(format!("Force fields: {}", self.forceFields.len()));
+				ui.text(format!("Obstacles: {}", self.obstacles.len()));
+				ui.text(format!("Sleeping: {}", self.sleepingCount()));
+				ui.text(format!("Contacts: {}", self.contactCount()));
+				ui.text(format!("Sensors: {}", self.sensors.len()));
+				ui.text(format!("Emitters: {}", self.emitters.len()));
+				ui.text(format!("Expired: {}", self.expired));
+				ui.text(format!("Killed: {}", self.killed));
+				if ui.collapsing_header("Constraints", TreeNodeFlags::COLLAPSING_HEADER) {
+					let mut removed = Vec::new();
+					let mut woken = Vec::new();
+					for constraint in self.constraints.iter() {
+						let mut constraint = constraint.write().unwrap();
+						let id = constraint.id();
+						let _id = ui.push_id(id);
+						if let Some(_node) = ui.tree_node(format!("{} {}", constraint.name(), id)) {
+							ui.group(|| constraint.gui(ui));
+							if ui.is_item_edited() {
+								woken.extend(constraint.links().into_iter().flat_map(|(id1, id2)| [id1, id2]));
+							}
+							if ui.small_button("Remove") {
+								removed.push(id);
+							}
+						}
+					}
+					for id in woken {
+						self.wake(id);
+					}
+					for id in removed {
+						self.removeConstraint(id);
+					}
+				}
+				
+				if ui.collapsing_header("Force fields", TreeNodeFlags::COLLAPSING_HEADER) {
+					let center = Vec3::ZERO;
+					if ui.small_button("Attractor") {
+						self.addForceField(newForceFieldRef(Attractor::new(center, 1.0e7)));
+					}
+					ui.same_line();
+					if ui.small_button("Vortex") {
+						self.addForceField(newForceFieldRef(Vortex::new(center, self.worldSize.x / 4.0, 500.0)));
+					}
+					ui.same_line();
+					if ui.small_button("Wind") {
+						self.addForceField(newForceFieldRef(Wind::new(Vec3::new(100.0, 0.0, 0.0))));
+					}
+					ui.same_line();
+					if ui.small_button("Drag") {
+						self.addForceField(newForceFieldRef(Drag::new(0.5)));
+					}
+					ui.same_line();
+					if ui.small_button("Region") {
+						let area = AABB::centered(center, self.worldSize / 2.0);
+						self.addForceField(newForceFieldRef(RegionField::new(area, Wind::new(Vec3::new(0.0, 500.0, 0.0)))));
+					}
+					
+					let mut removed = Vec::new();
+					let mut edited = false;
+					for field in self.forceFields.iter() {
+						let mut field = field.write().unwrap();
+						let id = field.id();
+						let _id = ui.push_id(id);
+						if let Some(_node) = ui.tree_node(format!("{} {}", field.name(), id)) {
+							ui.group(|| field.gui(ui));
+							edited |= ui.is_item_edited();
+							if ui.small_button("Remove") {
+								removed.push(id);
+							}
+						}
+					}
+					if edited {
+						self.wakeAll();
+					}
+					for id in removed {
+						self.removeForceField(id);
+					}
+				}
+				
+				if ui.collapsing_header("Soft bodies", TreeNodeFlags::COLLAPSING_HEADER) {
+					let radius = self.worldSize.x.min(self.worldSize.y) / 12.0;
+					let center = Vec3::new(0.0, self.worldSize.y / 4.0, 0.0);
+					let segments = 24;
+					let particleSize = radius * TAU / segments as f32;
+					if ui.small_button("Blob") {
+						self.addSoftBody(center, radius, segments, particleSize, Vec3::new(0.3, 0.8, 0.3));
+					}
+					ui.same_line();
+					if ui.small_button("Jelly") {
+						let id = self.addSoftBody(center, radius, segments, particleSize, Vec3::new(0.9, 0.3, 0.6));
+						self.editConstraint::<PressureConstraint, _>(id, |pressure| pressure.pressure *= 4.0);
+					}
+				}
+				
+				if ui.collapsing_header("Ropes and cloth", TreeNodeFlags::COLLAPSING_HEADER) {
+					let size = self.worldSize.x.min(self.worldSize.y);
+					let top = self.worldSize.y / 2.0 - size / 10.0;
+					if ui.small_button("Rope") {
+						let options = RopeOptions {
+							stiffness: 0.8,
+							..Default::default()
+						};
+						self.addRope(Vec3::new(-size / 4.0, top, 0.0), Vec3::new(0.0, top, 0.0), &options);
+					}
+					ui.same_line();
+					if ui.small_button("Chain") {
+						let options = RopeOptions {
+							segments: 30,
+							pinEnd: true,
+							..Default::default()
+						};
+						self.addRope(Vec3::new(-size / 4.0, top, 0.0), Vec3::new(size / 4.0, top, 0.0), &options);
+					}
+					ui.same_line();
+					if ui.small_button("Cloth") {
+						let clothSize = Vec3::new(size / 3.0, size / 4.0, 0.0);
+						self.addCloth(Vec3::new(0.0, top - clothSize.y / 2.0, 0.0), clothSize, &ClothOptions::default());
+					}
+				}
+				
+				if ui.collapsing_header("Obstacles", TreeNodeFlags::COLLAPSING_HEADER) {
+					let size = self.worldSize.x.min(self.worldSize.y);
+					if ui.small_button("Ramp") {
+						self.addObstacle(newObstacleRef(Obstacle::segment(Vec3::new(-size / 3.0, 0.0, 0.0), Vec3::new(size / 3.0, -size / 6.0, 0.0))));
+					}
+					ui.same_line();
+					if ui.small_button("Funnel") {
+						let (top, bottom) = (size / 4.0, size / 20.0);
+						self.addObstacle(newObstacleRef(Obstacle::segment(Vec3::new(-top, top, 0.0), Vec3::new(-bottom, 0.0, 0.0))));
+						self.addObstacle(newObstacleRef(Obstacle::segment(Vec3::new(top, top, 0.0), Vec3::new(bottom, 0.0, 0.0))));
+					}
+					ui.same_line();
+					if ui.small_button("Pegboard") {
+						let spacing = size / 12.0;
+						let peg = [Vec3::new(0.0, -1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)];
+						for row in 0..5 {
+							for column in 0..7 {
+								let offset = if row % 2 == 0 { 0.0 } else { spacing / 2.0 };
+								let center = Vec3::new((column as f32 - 3.0) * spacing + offset, -(row as f32) * spacing, 0.0);
+								if let Some(obstacle) = Obstacle::polygon(peg.iter().map(|v| center + *v * spacing / 8.0).collect()) {
+									self.addObstacle(newObstacleRef(obstacle));
+								}
+							}
+						}
+					}
+					ui.same_line();
+					if ui.small_button("Clear") {
+						self.obstacles.clear();
+						self.rebuildObstacleGrid();
+					}
+				}
+				
+				if ui.collapsing_header("Fluid", TreeNodeFlags::COLLAPSING_HEADER) {
+					let size = self.worldSize.x.min(self.worldSize.y);
+					if ui.small_button("Pool") {
+						let center = Vec3::new(0.0, -self.worldSize.y / 4.0, 0.0);
+						self.addFluid(center, Vec3::splat(size / 4.0), 3.0, Vec3::new(0.2, 0.4, 1.0));
+					}
+					ui.same_line();
+					if ui.small_button("Clear") {
+						self.removePhysicals(|physical| physical.fluid());
+					}
+					self.fluid.gui(ui);
+				}
+				
+				if ui.collapsing_header("Emitters", TreeNodeFlags::COLLAPSING_HEADER) {
+					let half = self.worldSize / 2.0;
+					if ui.small_button("Fountain") {
+						self.addEmitter(newEmitterRef(Emitter::new(Vec3::new(0.0, -half.y * 0.8, 0.0), Vec3::Y)));
+					}
+					ui.same_line();
+					if ui.small_button("Stream") {
+						let mut emitter = Emitter::new(Vec3::new(-half.x * 0.8, half.y * 0.6, 0.0), Vec3::X);
+						emitter.spread = 0.02;
+						emitter.speed = 300.0;
+						emitter.rate = 60.0;
+						emitter.minRadius = 3.0;
+						emitter.maxRadius = 3.0;
+						emitter.colorStart = Vec3::new(1.0, 0.4, 0.1);
+						emitter.colorEnd = Vec3::new(1.0, 0.9, 0.2);
+						self.addEmitter(newEmitterRef(emitter));
+					}
+					ui.same_line();
+					if ui.small_button("Tap") {
+						let mut emitter = Emitter::new(Vec3::new(0.0, half.y * 0.8, 0.0), -Vec3::Y);
+						emitter.spread = 0.05;
+						emitter.speed = 100.0;
+						emitter.rate = 120.0;
+						emitter.minRadius = 3.0;
+						emitter.maxRadius = 3.0;
+						emitter.colorStart = Vec3::new(0.2, 0.4, 1.0);
+						emitter.colorEnd = Vec3::new(0.4, 0.7, 1.0);
+						emitter.fluid = true;
+						self.addEmitter(newEmitterRef(emitter));
+					}
+					
+					let mut removed = Vec::new();
+					for emitter in self.emitters.iter() {
+						let mut emitter = emitter.write().unwrap();
+						let id = emitter.id();
+						let _id = ui.push_id(id);
+						if let Some(_node) = ui.tree_node(format!("Emitter {}", id)) {
+							emitter.gui(ui);
+							if ui.small_button("Remove") {
+								removed.push(id);
+							}
+						}
+					}
+					for id in removed {
+						self.removeEmitter(id);
+					}
+				}
+				
+				if ui.collapsing_header("Kill zones", TreeNodeFlags::COLLAPSING_HEADER) {
+					let half = self.worldSize / 2.0;
+					if ui.small_button("Floor") {
+						let size = Vec3::new(self.worldSize.x, self.worldSize.y / 20.0, 0.0);
+						self.addKillZone(newKillZoneRef(KillZone::new(SensorArea::Aabb(AABB::new(Vec3::new(-half.x, -half.y, 0.0), size)))));
+					}
+					ui.same_line();
+					if ui.small_button("Drain") {
+						let radius = self.worldSize.x / 16.0;
+						self.addKillZone(newKillZoneRef(KillZone::new(SensorArea::Circle { center: Vec3::new(0.0, -half.y, 0.0), radius })));
+					}
+					ui.same_line();
+					if ui.small_button("Reset counters") {
+						self.expired = 0;
+						self.killed = 0;
+					}
+					
+					let mut removed = Vec::new();
+					for zone in self.killZones.iter() {
+						let mut zone = zone.write().unwrap();
+						let id = zone.id();
+						let _id = ui.push_id(id);
+						if let Some(_node) = ui.tree_node(format!("{} {}", zone.area.name(), id)) {
+							zone.gui(ui);
+							if ui.small_button("Remove") {
+								removed.push(id);
+							}
+						}
+					}
+					for id in removed {
+						self.removeKillZone(id);
+					}
+				}
+				
+				if ui.collapsing_header("Sensors", TreeNodeFlags::COLLAPSING_HEADER) {
+					let half = self.worldSize / 2.0;
+					if ui.small_button("Goal") {
+						let size = Vec3::new(self.worldSize.x / 4.0, self.worldSize.y / 10.0, 0.0);
+						self.addSensor(newSensorRef(Sensor::new(SensorArea::Aabb(AABB::new(Vec3::new(-size.x / 2.0, -half.y, 0.0), size)))));
+					}
+					ui.same_line();
+					if ui.small_button("Circle") {
+						self.addSensor(newSensorRef(Sensor::new(SensorArea::Circle { center: Vec3::ZERO, radius: self.worldSize.x / 8.0 })));
+					}
+					ui.same_line();
+					if ui.small_button("Line") {
+						// Thin strip across the world counting everything falling through its middle
+						let thickness = 2.0;
+						self.addSensor(newSensorRef(Sensor::new(SensorArea::Polygon(vec![
+							Vec3::new(-half.x, -thickness, 0.0),
+							Vec3::new(half.x, -thickness, 0.0),
+							Vec3::new(half.x, thickness, 0.0),
+							Vec3::new(-half.x, thickness, 0.0),
+						]))));
+					}
+					
+					let mut removed = Vec::new();
+					for sensor in self.sensors.iter() {
+						let mut sensor = sensor.write().unwrap();
+						let id = sensor.id();
+						let _id = ui.push_id(id);
+						if let Some(_node) = ui.tree_node(format!("{} {}", sensor.area.name(), id)) {
+							sensor.gui(ui);
+							if ui.small_button("Remove") {
+								removed.push(id);
+							}
+						}
+					}
+					for id in removed {
+						self.removeSensor(id);
+					}
+				}
+				
+				if ui.collapsing_header("Contacts", TreeNodeFlags::COLLAPSING_HEADER) {
+					let [begin, persist, end] = self.contactEventCounts;
+					ui.text(format!("Last update: {} begin, {} persist, {} end", begin, persist, end));
+					ui.text(format!("Listeners: {}", self.contactListeners.len()));
+					if ui.small_button("Clear log") {
+						self.contactLog.clear();
+					}
+					for event in self.contactLog.iter().rev() {
+						let contact = event.contact();
+						ui.text(format!("{} {} - {} depth {:.2}", event.name(), contact.id1, contact.id2, contact.depth));
+					}
+				}
+				
+				if ui.collapsing_header("Container", TreeNodeFlags::COLLAPSING_HEADER) {
+					let mut container = self.container.clone();
+					container.gui(ui, self.worldSize);
+					if container != self.container {
+						self.setContainer(container);
+					}
+				}
 				
 				let mut collisionMode = self.flags.get(F_COLLISION_MODE);
 				let mut threadMode = self.flags.get(F_THREAD_MODE);
 				if ui.checkbox("Use threads", &mut threadMode) {
 					self.flags.flip(F_THREAD_MODE);
 				}
+				let mut deterministic = self.isDeterministic();
+				if ui.checkbox("Deterministic", &mut deterministic) {
+					self.setDeterministic(deterministic);
+				}
+				let mut ccd = self.isCcd();
+				if ui.checkbox("Continuous collision", &mut ccd) {
+					self.setCcd(ccd);
+				}
+				let mut sleep = self.isSleepMode();
+				if ui.checkbox("Sleeping", &mut sleep) {
+					self.setSleepMode(sleep);
+				}
+				if sleep {
+					ui.input_float("Sleep speed", &mut self.sleepSpeed);
+					ui.input_float("Sleep time", &mut self.sleepTime);
+					self.sleepSpeed = self.sleepSpeed.max(0.0);
+					self.sleepTime = self.sleepTime.max(0.0);
+				}
 				
 				if threadMode {
-					// ui.text(format!("Threads: {}/{}", self.threadPool.getActive(), self.threadPool.getTotal()));
-					ui.text(format!("Threads: {}", self.threadPool.getTotal()));
+					// ui.text(format!("Threads: {}/{}", self.threadPool.getActive(), THREAD_COUNT));
+					ui.text(format!("Threads: {}", THREAD_COUNT));
 				} else {
 					if ui.checkbox("Space partition/Sweep n' prune", &mut collisionMode) {
 						self.flags.flip(F_COLLISION_MODE);
@@ -778,51 +2405,518 @@ impl Solver {
 			});
 	}
 	
-	pub fn getPhysicals(&self) -> &HashMap<usize, PhysicalRef> {
+	pub fn getPhysicals(&self) -> &BTreeMap<usize, PhysicalRef> {
 		&self.physicals
 	}
-
-	// Semi-Redundant since render manager destroys all renderables, here in case of multiple solvers
+	
+	pub fn getBoxes(&self) -> &Vec<ConstraintBox> {
+		&self.boxes
+	}
+	
+	pub fn getConstraints(&self) -> &Vec<ConstraintRef> {
+		&self.constraints
+	}
+	
+	pub fn getForceFields(&self) -> &Vec<ForceFieldRef> {
+		&self.forceFields
+	}
+	
+	pub fn getObstacles(&self) -> &Vec<ObstacleRef> {
+		&self.obstacles
+	}
+	
+	pub fn getSensors(&self) -> &Vec<SensorRef> {
+		&self.sensors
+	}
+	
+	pub fn getEmitters(&self) -> &Vec<EmitterRef> {
+		&self.emitters
+	}
+	
+	pub fn getKillZones(&self) -> &Vec<KillZoneRef> {
+		&self.killZones
+	}
+	
+	pub fn getPartitionTree(&self) -> &BSPGrid<PhysicalRef> {
+		&self.quadTree
+	}
+	
+	pub fn visitChunkTrees<E, F: FnMut(&BSPGrid<PhysicalRef>) -> Result<(), E>>(&self, mut visit: F) -> Result<(), E> {
+		for chunk in self.chunks.iter() {
+			visit(&chunk.read().unwrap().tree)?;
+		}
+		Ok(())
+	}
+	
 	pub fn destroy(&mut self) {
 		self.flags.set(F_DESTROYED);
-		self.mesh.borrow_mut().destroy();
 		self.threadPool.stopAll();
 	}
 }
 
-impl Renderable for Solver {
-	fn meshRef(&self) -> Option<&MeshRef> {
-		Some(&self.mesh)
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+	use std::sync::{Arc, Mutex};
+	use glam::Vec3;
+	use crate::simulation::ball::Ball;
+	use crate::simulation::contact::ContactEvent;
+	use crate::simulation::container::Container;
+	use crate::simulation::emitter::Emitter;
+	use crate::simulation::obstacle::Obstacle;
+	use crate::simulation::Physical;
+	use crate::simulation::region::AABB;
+	use crate::simulation::sensor::{KillZone, Sensor, SensorArea};
+	use crate::types::{newEmitterRef, newKillZoneRef, newObstacleRef, newPhysicalRef, newSensorRef};
+	use super::Solver;
+	
+	const DT: f32 = 1.0 / 60.0;
+	
+	/// Grid of balls over the middle of the world, falling under gravity
+	fn ballGrid(solver: &mut Solver, columns: usize, rows: usize, size: f32) {
+		for y in 0..rows {
+			for x in 0..columns {
+				let position = Vec3::new((x as f32 - columns as f32 / 2.0) * size * 1.5, (y as f32 - rows as f32 / 2.0) * size * 1.5, 0.0);
+				solver.addPhysical(newPhysicalRef(Ball::new(position, Vec3::splat(size))));
+			}
+		}
+	}
+	
+	fn fallingScene(threaded: bool) -> Solver {
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.gravity = Vec3::new(0.0, -400.0, 0.0);
+		solver.setThreadMode(threaded);
+		solver.pause(false);
+		ballGrid(&mut solver, 10, 10, 10.0);
+		solver
+	}
+	
+	#[test]
+	fn ballsFallAndStayInsideTheWorld() {
+		for threaded in [false, true] {
+			let mut solver = fallingScene(threaded);
+			let startY: f32 = solver.getPhysicals().values().map(|physical| physical.read().unwrap().transform().position.y).sum();
+			for _ in 0..120 {
+				solver.update(DT);
+			}
+			
+			assert_eq!(solver.getPhysicals().len(), 100);
+			let half = solver.worldSize / 2.0;
+			let mut endY = 0.0;
+			for physical in solver.getPhysicals().values() {
+				let physical = physical.read().unwrap();
+				let position = physical.transform().position;
+				assert!(position.is_finite());
+				assert!(position.x.abs() <= half.x && position.y.abs() <= half.y, "{:?} left the world", position);
+				endY += position.y;
+			}
+			assert!(endY < startY, "balls didn't fall");
+		}
+	}
+	
+	/// Position and last position bits of every physical, in id order
+	fn snapshot(solver: &Solver) -> Vec<[u32; 6]> {
+		solver.getPhysicals().values().map(|physical| {
+			let physical = physical.read().unwrap();
+			let (position, last) = (physical.transform().position, physical.lastTransform().position);
+			[position.x, position.y, position.z, last.x, last.y, last.z].map(f32::to_bits)
+		}).collect()
+	}
+	
+	fn deterministicRun(threaded: bool, updates: usize) -> Vec<[u32; 6]> {
+		let mut solver = fallingScene(threaded);
+		solver.setDeterministic(true);
+		for _ in 0..updates {
+			solver.update(DT);
+		}
+		snapshot(&solver)
+	}
+	
+	#[test]
+	fn deterministicReplayIsBitIdentical() {
+		assert_eq!(deterministicRun(false, 120), deterministicRun(false, 120));
+	}
+	
+	#[test]
+	fn deterministicThreadedReplayIsBitIdentical() {
+		assert_eq!(deterministicRun(true, 120), deterministicRun(true, 120));
+	}
+	
+	/// Ball moving 500 units per update along x, 62.5 a sub step, ccd on and nothing else in the world
+	fn fastBallScene(threaded: bool) -> (Solver, usize) {
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.setThreadMode(threaded);
+		solver.setCcd(true);
+		solver.pause(false);
+		let mut ball = Ball::new(Vec3::new(-300.0, 0.0, 0.0), Vec3::splat(10.0));
+		ball.setVelocity(Vec3::new(30000.0, 0.0, 0.0), DT / 8.0);
+		let id = ball.id();
+		solver.addPhysical(newPhysicalRef(ball));
+		(solver, id)
+	}
+	
+	#[test]
+	fn ccdKeepsSpeedWithoutAHit() {
+		for threaded in [false, true] {
+			let (mut solver, id) = fastBallScene(threaded);
+			solver.setContainer(Container::None);
+			for _ in 0..3 {
+				solver.update(DT);
+			}
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			assert!((position.x - 1200.0).abs() < 1.0, "ball ended at {:?}", position);
+		}
+	}
+	
+	#[test]
+	fn ccdStopsAtThinObstacles() {
+		for threaded in [false, true] {
+			let (mut solver, id) = fastBallScene(threaded);
+			solver.addObstacle(newObstacleRef(Obstacle::segment(Vec3::new(0.0, -100.0, 0.0), Vec3::new(0.0, 100.0, 0.0))));
+			for _ in 0..3 {
+				solver.update(DT);
+			}
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			assert!(position.x < 0.0, "ball tunnelled to {:?}", position);
+		}
+	}
+	
+	#[test]
+	fn ccdStopsAtTheContainer() {
+		for threaded in [false, true] {
+			let (mut solver, id) = fastBallScene(threaded);
+			solver.setContainer(Container::Circle { radius: 400.0 });
+			for _ in 0..3 {
+				solver.update(DT);
+			}
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			assert!(position.length() <= 400.0, "ball left the container at {:?}", position);
+		}
+	}
+	
+	#[test]
+	fn queriesFindPhysicalsOutsideTheWorld() {
+		for threaded in [false, true] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.setThreadMode(threaded);
+			solver.setContainer(Container::None);
+			solver.pause(false);
+			let ball = Ball::new(Vec3::new(3000.0, 0.0, 0.0), Vec3::splat(10.0));
+			let id = ball.id();
+			solver.addPhysical(newPhysicalRef(ball));
+			assert_eq!(solver.queryPoint(Vec3::new(3000.0, 0.0, 0.0)), vec![id]);
+			
+			solver.update(DT);
+			assert_eq!(solver.queryPoint(Vec3::new(3000.0, 0.0, 0.0)), vec![id]);
+			assert!(solver.raycast(Vec3::new(2000.0, 0.0, 0.0), Vec3::X, 2000.0).is_some_and(|hit| hit.id == id));
+		}
+	}
+	
+	#[test]
+	fn queriesFollowPhysicalsBetweenUpdates() {
+		for threaded in [false, true] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.setThreadMode(threaded);
+			solver.pause(false);
+			// Enough physicals to split the trees, so a stale tree puts the ball in the wrong node
+			ballGrid(&mut solver, 10, 2, 10.0);
+			let mut ball = Ball::new(Vec3::new(-300.0, 300.0, 0.0), Vec3::splat(10.0));
+			ball.setVelocity(Vec3::new(12000.0, 0.0, 0.0), DT / 8.0);
+			let id = ball.id();
+			solver.addPhysical(newPhysicalRef(ball));
+			
+			solver.update(DT);
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			assert!(position.x > -150.0, "ball only got to {:?}", position);
+			assert_eq!(solver.queryPoint(position), vec![id]);
+			assert_eq!(solver.queryCircle(position, 1.0), vec![id]);
+		}
+	}
+	
+	#[test]
+	fn sleepingPhysicalsStayOutOfTheTrees() {
+		for threaded in [false, true] {
+			let mut solver = fallingScene(threaded);
+			solver.setSleepMode(true);
+			for _ in 0..600 {
+				solver.update(DT);
+			}
+			assert!(solver.sleepingCount() > 0, "nothing fell asleep");
+			
+			// Trees are rebuilt at the start of an update, so only bodies asleep by then are left out
+			let id = *solver.sleepingIds.first().expect("sleeping tree is empty");
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			let inTrees = if threaded {
+				solver.chunks.iter().any(|chunk| chunk.read().unwrap().physicals.iter().any(|physical| physical.read().unwrap().id() == id))
+			} else {
+				let area = AABB::centered(position, Vec3::splat(1.0));
+				solver.quadTree.findInArea(&area, &|_, _| true).iter().any(|physical| physical.read().unwrap().id() == id)
+			};
+			assert!(!inTrees, "sleeping physical was put in the trees");
+			assert!(solver.queryPoint(position).contains(&id));
+		}
+	}
+	
+	#[test]
+	fn solverIsSend() {
+		fn assertSend<T: Send>(_: &T) {}
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.addContactListener(|_| {});
+		assertSend(&solver);
+	}
+	
+	#[test]
+	fn boxesRespectLayersAndMasks() {
+		for mask in [u32::MAX, 0] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.pause(false);
+			solver.addBox(Vec3::ZERO, Vec3::splat(100.0), 4.0, Vec3::ONE);
+			let mut ball = Ball::new(Vec3::new(10.0, 0.0, 0.0), Vec3::splat(10.0));
+			ball.mask = mask;
+			let id = ball.id();
+			solver.addPhysical(newPhysicalRef(ball));
+			solver.update(DT);
+			
+			let position = solver.getPhysicals()[&id].read().unwrap().transform().position;
+			if mask == 0 {
+				assert!(position.x.abs() < 50.0, "ball masked out of the box was pushed to {:?}", position);
+			} else {
+				assert!(position.x.abs() >= 50.0, "ball stayed inside the box at {:?}", position);
+			}
+		}
 	}
 	
-	fn shaderRef(&self) -> Option<&ShaderRef> {
-		Some(&self.shader)
+	#[test]
+	fn emitterIntoAFullContainerStopsSpawning() {
+		for threaded in [false, true] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.setThreadMode(threaded);
+			solver.setContainer(Container::Circle { radius: 50.0 });
+			solver.pause(false);
+			let mut emitter = Emitter::new(Vec3::ZERO, Vec3::Y);
+			emitter.rate = 2000.0;
+			emitter.maxCount = 100000;
+			solver.addEmitter(newEmitterRef(emitter));
+			for _ in 0..300 {
+				solver.update(DT);
+			}
+			
+			// A circle of 50 fits a couple of hundred balls of radius 4 to 6, not thousands
+			let count = solver.getPhysicals().len();
+			assert!(count > 0 && count < 300, "{} balls in the container", count);
+		}
 	}
 	
-	fn renderPost(&self, projViewMat: &Mat4, dt: f32, lineRenderer: &mut LineRenderer) -> Result<(), String> {
-		let collisionMode = self.flags.get(F_COLLISION_MODE);
-		let threadMode = self.flags.get(F_THREAD_MODE);
+	#[test]
+	fn killZonesAreSharedWithTheCaller() {
+		let mut solver = fallingScene(false);
+		let zone = newKillZoneRef(KillZone::new(SensorArea::Aabb(AABB::new(Vec3::new(-500.0, -500.0, 0.0), Vec3::new(1000.0, 100.0, 0.0)))));
+		let id = zone.read().unwrap().id();
+		solver.addKillZone(zone.clone());
+		for _ in 0..120 {
+			solver.update(DT);
+		}
 		
-		if threadMode {
-			for chunk in self.chunks.iter() {
-				let chunk = chunk.read().unwrap();
-				chunk.tree.render(projViewMat, dt, lineRenderer)?;
-				
-				lineRenderer.pushAABB(chunk.tree.bounds(), Vec3::Z);
-				// for neighbour in chunk.neighbours.iter() {
-				// 	let cPos = chunk.tree.bounds().center();
-				// 	let nPos = neighbour.read().unwrap().tree.bounds().center();
-				// 	let dir = nPos - cPos;
-				// 	lineRenderer.pushLine3(cPos, Vec3::Z, cPos + dir / 2.0, Vec3::ONE);
-				// }
+		let killed = zone.read().unwrap().killed();
+		assert!(killed > 0);
+		assert_eq!(solver.getKillZone(id).unwrap().read().unwrap().killed(), killed);
+		assert_eq!(solver.killedCount(), killed);
+		assert_eq!(solver.getPhysicals().len(), 100 - killed);
+		assert!(solver.removeKillZone(id));
+		assert!(solver.getKillZone(id).is_none());
+	}
+	
+	#[test]
+	fn stackedBoxesKeepTheirSpacing() {
+		for threaded in [false, true] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.gravity = Vec3::new(0.0, -400.0, 0.0);
+			solver.setThreadMode(threaded);
+			solver.pause(false);
+			// Dropped with gaps so every box lands on the one below
+			let boxes: Vec<usize> = (0..5).map(|i| {
+				solver.addBox(Vec3::new(0.0, -400.0 + i as f32 * 80.0, 0.0), Vec3::splat(50.0), 4.0, Vec3::ONE)
+			}).collect();
+			for _ in 0..300 {
+				solver.update(DT);
+			}
+			
+			let heights: Vec<f32> = boxes.iter().map(|id| {
+				let constraintBox = solver.getBoxes().iter().find(|constraintBox| constraintBox.id() == *id).unwrap();
+				let polygon = constraintBox.polygon(solver.getPhysicals()).unwrap();
+				polygon.iter().map(|corner| corner.y).sum::<f32>() / polygon.len() as f32
+			}).collect();
+			for pair in heights.windows(2) {
+				assert!((pair[1] - pair[0] - 54.0).abs() < 5.0, "boxes collapsed to heights {:?}", heights);
 			}
-		} else if collisionMode {
-			self.quadTree.render(projViewMat, dt, lineRenderer)?;
 		}
-		Ok(())
 	}
 	
-	fn modelMatrix(&self) -> Mat4 {
-		Mat4::from_scale(self.worldSize)
+	#[test]
+	fn forcesWakeSleepingBodies() {
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.gravity = Vec3::new(0.0, -400.0, 0.0);
+		solver.pause(false);
+		let ball = Ball::new(Vec3::new(0.0, -490.0, 0.0), Vec3::splat(20.0));
+		let id = ball.id();
+		solver.addPhysical(newPhysicalRef(ball));
+		for _ in 0..120 {
+			solver.update(DT);
+		}
+		let ball = solver.getPhysicals()[&id].clone();
+		assert!(ball.read().unwrap().sleeping(), "ball never fell asleep");
+		
+		let start = ball.read().unwrap().transform().position;
+		ball.write().unwrap().applyForce(Vec3::new(1e6, 0.0, 0.0));
+		assert!(!ball.read().unwrap().sleeping());
+		solver.update(DT);
+		assert!(ball.read().unwrap().transform().position.x > start.x + 1.0, "ball ignored the force");
+	}
+	
+	#[test]
+	fn expiredBodiesEndTheirContactsRightAway() {
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.gravity = Vec3::new(0.0, -400.0, 0.0);
+		// Awake, so the pair is found again in the update the ball expires
+		solver.setSleepMode(false);
+		solver.pause(false);
+		solver.addPhysical(newPhysicalRef(Ball::new(Vec3::new(0.0, -490.0, 0.0), Vec3::splat(20.0))));
+		let mut ball = Ball::new(Vec3::new(0.0, -470.0, 0.0), Vec3::splat(20.0));
+		ball.setLifetime(Some(1.0));
+		let id = ball.id();
+		solver.addPhysical(newPhysicalRef(ball));
+		
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let sink = events.clone();
+		solver.addContactListener(move |event| sink.lock().unwrap().push(*event));
+		
+		let mut removedAt = None;
+		for update in 0..90 {
+			events.lock().unwrap().clear();
+			solver.update(DT);
+			let events = events.lock().unwrap();
+			let mine: Vec<&ContactEvent> = events.iter().filter(|event| event.contact().id1 == id || event.contact().id2 == id).collect();
+			if removedAt.is_none() && !solver.getPhysicals().contains_key(&id) {
+				removedAt = Some(update);
+				assert!(!mine.is_empty() && mine.iter().all(|event| matches!(event, ContactEvent::End(_))), "expected only an end event, got {:?}", mine);
+			} else if removedAt.is_some() {
+				assert!(mine.is_empty(), "events for a removed body: {:?}", mine);
+			}
+		}
+		assert!(removedAt.is_some(), "ball never expired");
+		assert_eq!(solver.contactCount(), 0);
+	}
+	
+	#[test]
+	fn removedBodiesLeaveNothingBehind() {
+		for threaded in [false, true] {
+			let mut solver = fallingScene(threaded);
+			solver.addBox(Vec3::new(0.0, 200.0, 0.0), Vec3::splat(60.0), 6.0, Vec3::ONE);
+			let softBody = solver.addSoftBody(Vec3::new(-150.0, 200.0, 0.0), 40.0, 12, 8.0, Vec3::ONE);
+			let mut ids: HashSet<usize> = solver.getBoxes()[0].corners.iter().copied().collect();
+			ids.extend(solver.getConstraint(softBody).unwrap().read().unwrap().links().iter().map(|(id, _)| *id));
+			let touchingRemoved = |solver: &Solver| solver.touching.keys().any(|(id1, id2)| ids.contains(id1) || ids.contains(id2));
+			// Removed while they still touch something, so there are contacts to clean up
+			for _ in 0..600 {
+				solver.update(DT);
+				if touchingRemoved(&solver) {
+					break;
+				}
+			}
+			assert!(touchingRemoved(&solver), "removed bodies never landed");
+			let positions: Vec<Vec3> = ids.iter().map(|id| solver.getPhysicals()[id].read().unwrap().transform().position).collect();
+			
+			assert_eq!(solver.removePhysicals(|physical| ids.contains(&physical.id())).len(), ids.len());
+			assert!(solver.getBoxes().is_empty());
+			assert!(solver.getConstraints().iter().all(|constraint| {
+				constraint.read().unwrap().links().iter().all(|(id1, id2)| !ids.contains(id1) && !ids.contains(id2))
+			}), "constraints still link removed bodies");
+			for position in positions {
+				assert!(solver.queryCircle(position, 10.0).iter().all(|id| !ids.contains(id)), "removed body still found at {:?}", position);
+			}
+			assert!(!touchingRemoved(&solver));
+			
+			for _ in 0..60 {
+				solver.update(DT);
+			}
+			assert_eq!(solver.getPhysicals().len(), 100);
+			assert!(!touchingRemoved(&solver));
+		}
+	}
+	
+	#[test]
+	fn sensorsReportBallsEnteringStayingAndLeaving() {
+		let mut solver = Solver::new(Vec3::splat(1000.0));
+		solver.setContainer(Container::None);
+		solver.pause(false);
+		let sensor = newSensorRef(Sensor::new(SensorArea::Aabb(AABB::centered(Vec3::ZERO, Vec3::splat(100.0)))));
+		solver.addSensor(sensor.clone());
+		let mut ball = Ball::new(Vec3::new(-200.0, 0.0, 0.0), Vec3::splat(10.0));
+		// Positions are integrated once per sub step
+		ball.setVelocity(Vec3::new(300.0, 0.0, 0.0), DT / 8.0);
+		let id = ball.id();
+		solver.addPhysical(newPhysicalRef(ball));
+		
+		// Which list the ball was in after each update, in order with repeats collapsed
+		let mut phases = Vec::new();
+		let mut stayed = 0;
+		for _ in 0..120 {
+			solver.update(DT);
+			let sensor = sensor.read().unwrap();
+			let phase = match (sensor.entered(), sensor.stayed(), sensor.left()) {
+				([entered], [], []) if *entered == id => "entered",
+				([], [stayed], []) if *stayed == id => "stayed",
+				([], [], [left]) if *left == id => "left",
+				([], [], []) => "outside",
+				lists => panic!("unexpected sensor lists {:?}", lists),
+			};
+			assert_eq!(sensor.inside().contains(&id), phase == "entered" || phase == "stayed");
+			stayed += (phase == "stayed") as usize;
+			if phases.last() != Some(&phase) {
+				phases.push(phase);
+			}
+		}
+		assert_eq!(phases, ["outside", "entered", "stayed", "left", "outside"]);
+		assert!(stayed > 10, "ball only stayed {} updates", stayed);
+		assert_eq!(sensor.read().unwrap().entries(), 1);
+	}
+	
+	#[test]
+	fn fluidPressureKeepsParticlesApart() {
+		for threaded in [false, true] {
+			let mut solver = Solver::new(Vec3::splat(1000.0));
+			solver.gravity = Vec3::new(0.0, -400.0, 0.0);
+			solver.setThreadMode(threaded);
+			solver.pause(false);
+			// Fluid particles don't collide with each other, without pressure they land on one spot
+			let ids = solver.addFluid(Vec3::new(0.0, -450.0, 0.0), Vec3::splat(60.0), 3.0, Vec3::ONE);
+			for _ in 0..150 {
+				solver.update(DT);
+			}
+			
+			let positions: Vec<Vec3> = ids.iter().map(|id| solver.getPhysicals()[id].read().unwrap().transform().position).collect();
+			let nearest: Vec<f32> = positions.iter().enumerate().map(|(i, position)| {
+				positions.iter().enumerate()
+					.filter(|(j, _)| *j != i)
+					.map(|(_, other)| position.distance(*other))
+					.fold(f32::MAX, f32::min)
+			}).collect();
+			let mean = nearest.iter().sum::<f32>() / nearest.len() as f32;
+			let crowded = nearest.iter().filter(|distance| **distance < 2.0).count();
+			assert!(mean > 2.0, "particles are {} apart on average", mean);
+			assert!(crowded < ids.len() / 2, "{} of {} particles collapsed onto a neighbour", crowded, ids.len());
+		}
 	}
-}
\ No newline at end of file
+	
+	#[test]
+	fn pausedSolverDoesNotMove() {
+		let mut solver = fallingScene(false);
+		solver.pause(true);
+		let before: Vec<Vec3> = solver.getPhysicals().values().map(|physical| physical.read().unwrap().transform().position).collect();
+		for _ in 0..10 {
+			solver.update(DT);
+		}
+		let after: Vec<Vec3> = solver.getPhysicals().values().map(|physical| physical.read().unwrap().transform().position).collect();
+		assert_eq!(before, after);
+	}
+}
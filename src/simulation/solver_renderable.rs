@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use glam::{Mat4, Vec3};
+use crate::graphics::{LineRenderer, Renderable};
+use crate::graphics::mesh::{Mesh, Vertex};
+use crate::simulation::container::Container;
+use crate::types::{newMeshRef, GlRef, MeshRef, ShaderRef, SolverRef};
+
+/// World container shape, segment obstacles, sensors, kill zones, emitters and debug trees of a solver
+pub struct SolverRenderable {
+	mesh: MeshRef,
+	shader: ShaderRef,
+	verletSolver: SolverRef,
+	/// Container and world size the mesh was last built from
+	container: RefCell<(Container, Vec3)>,
+}
+
+impl SolverRenderable {
+	pub fn new(gl: GlRef, shader: ShaderRef, verletSolver: SolverRef) -> Result<Self, String> {
+		let (container, worldSize) = {
+			let solver = verletSolver.borrow();
+			(solver.getContainer().clone(), solver.worldSize)
+		};
+		let (vertices, indices) = Self::data(&container, worldSize);
+		let mut mesh = Mesh::simple(gl, vertices, Some(indices));
+		mesh.upload(shader.clone())?;
+		
+		Ok(Self {
+			mesh: newMeshRef(mesh),
+			shader,
+			verletSolver,
+			container: RefCell::new((container, worldSize)),
+		})
+	}
+	
+	/// Triangle fan over the container outline, containers are convex
+	fn data(container: &Container, worldSize: Vec3) -> (Vec<Vertex>, Vec<u32>) {
+		let outline = container.outline(worldSize);
+		let vertices = outline.iter().map(|position| Vertex {
+			position: *position,
+			color: Vec3::splat(0.15),
+		}).collect();
+		let mut indices = Vec::new();
+		for i in 1..outline.len().saturating_sub(1) as u32 {
+			indices.extend_from_slice(&[0, i, i + 1]);
+		}
+		(vertices, indices)
+	}
+}
+
+impl Renderable for SolverRenderable {
+	fn meshRef(&self) -> Option<&MeshRef> {
+		Some(&self.mesh)
+	}
+	
+	fn shaderRef(&self) -> Option<&ShaderRef> {
+		Some(&self.shader)
+	}
+	
+	fn render(&self, projViewMat: &Mat4, dt: f32, lineRenderer: &mut LineRenderer) -> Result<(), String> {
+		{
+			let solver = self.verletSolver.borrow();
+			let mut mesh = self.mesh.borrow_mut();
+			let mut cached = self.container.borrow_mut();
+			if cached.0 != *solver.getContainer() || cached.1 != solver.worldSize {
+				let (vertices, indices) = Self::data(solver.getContainer(), solver.worldSize);
+				mesh.updateData(vertices, Some(indices))?;
+				*cached = (solver.getContainer().clone(), solver.worldSize);
+			}
+			
+			if cached.0 != Container::None {
+				let shader = self.shader.read().unwrap();
+				shader.bind();
+				shader.setMatrix4f("u_pvm", projViewMat);
+				mesh.draw();
+			}
+		}
+		self.renderPost(projViewMat, dt, lineRenderer)
+	}
+	
+	fn renderPost(&self, projViewMat: &Mat4, dt: f32, lineRenderer: &mut LineRenderer) -> Result<(), String> {
+		let solver = self.verletSolver.borrow();
+		
+		if solver.isThreadMode() {
+			solver.visitChunkTrees(|tree| -> Result<(), String> {
+				tree.render(projViewMat, dt, lineRenderer)?;
+				
+				lineRenderer.pushAABB(tree.bounds(), Vec3::Z);
+				Ok(())
+			})?;
+		} else if solver.isPartitionMode() {
+			solver.getPartitionTree().render(projViewMat, dt, lineRenderer)?;
+		}
+		
+		// Polygon obstacles are filled by the polygon renderable
+		for obstacle in solver.getObstacles().iter() {
+			if let [start, end] = obstacle.vertices() {
+				lineRenderer.pushLine3(*start, obstacle.color, *end, obstacle.color);
+			}
+		}
+		
+		let sensors = solver.getSensors().iter().map(|sensor| {
+			let sensor = sensor.read().unwrap();
+			(sensor.area.outline(), sensor.color)
+		});
+		let killZones = solver.getKillZones().iter().map(|zone| {
+			let zone = zone.read().unwrap();
+			(zone.area.outline(), zone.color)
+		});
+		for (outline, color) in sensors.chain(killZones) {
+			for i in 0..outline.len() {
+				lineRenderer.pushLine3(outline[i], color, outline[(i + 1) % outline.len()], color);
+			}
+		}
+		
+		// Short arrow along each emitter's direction
+		for emitter in solver.getEmitters().iter() {
+			let emitter = emitter.read().unwrap();
+			let end = emitter.position + emitter.direction.normalize_or_zero() * emitter.maxRadius.max(1.0) * 4.0;
+			lineRenderer.pushLine3(emitter.position, emitter.colorStart, end, emitter.colorEnd);
+		}
+		
+		let physicals = solver.getPhysicals();
+		for constraint in solver.getConstraints().iter() {
+			let constraint = constraint.read().unwrap();
+			let color = constraint.color();
+			for (id1, id2) in constraint.links() {
+				if let (Some(physical1), Some(physical2)) = (physicals.get(&id1), physicals.get(&id2)) {
+					let pos1 = physical1.read().unwrap().transform().position;
+					let pos2 = physical2.read().unwrap().transform().position;
+					lineRenderer.pushLine3(pos1, color, pos2, color);
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Drop for SolverRenderable {
+	fn drop(&mut self) {
+		self.destroy();
+	}
+}
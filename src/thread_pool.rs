@@ -110,10 +110,6 @@ impl ThreadPool {
 	// 	let (lock, _) = &*self.jobCount;
 	// 	*lock.lock().unwrap()
 	// }
-	
-	pub fn getTotal(&self) -> usize {
-		self.workers.len()
-	}
 }
 
 impl Drop for ThreadPool {
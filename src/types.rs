@@ -1,43 +1,65 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
+#[cfg(feature = "graphics")]
 use glow::{Context as GlowContext};
-use sdl3::video::Window as SdlWindow;
+#[cfg(feature = "graphics")]
 use crate::graphics::mesh::Mesh;
+#[cfg(feature = "graphics")]
 use crate::graphics::Renderable;
+#[cfg(feature = "graphics")]
 use crate::graphics::shader::Shader;
 use crate::simulation::{Physical, Solver};
+use crate::simulation::constraint::Constraint;
+use crate::simulation::emitter::Emitter;
+use crate::simulation::force_field::ForceField;
+use crate::simulation::obstacle::Obstacle;
+use crate::simulation::sensor::{KillZone, Sensor};
 
-pub type SdlWindowRef = Rc<RefCell<SdlWindow>>;
-
+#[cfg(feature = "graphics")]
 pub type GlRef = Arc<GlowContext>;
 
+#[cfg(feature = "graphics")]
 pub type ShaderRef = Arc<RwLock<Shader>>;
 
+#[cfg(feature = "graphics")]
 pub type RenderableRef = Rc<RefCell<dyn Renderable>>;
 
+#[cfg(feature = "graphics")]
 pub type MeshRef = Rc<RefCell<Mesh>>;
 
 pub type PhysicalRef = Arc<RwLock<dyn Physical>>;
 
-pub type SolverRef = Rc<RefCell<Solver>>;
+pub type ConstraintRef = Arc<RwLock<dyn Constraint>>;
 
-pub fn newSdlWindowRef(window: SdlWindow) -> SdlWindowRef {
-	Rc::new(RefCell::new(window))
-}
+pub type ForceFieldRef = Arc<RwLock<dyn ForceField>>;
+
+pub type ObstacleRef = Arc<Obstacle>;
+
+pub type SensorRef = Arc<RwLock<Sensor>>;
+
+pub type KillZoneRef = Arc<RwLock<KillZone>>;
 
+pub type EmitterRef = Arc<RwLock<Emitter>>;
+
+pub type SolverRef = Rc<RefCell<Solver>>;
+
+#[cfg(feature = "graphics")]
 pub fn newGlRef(gl: GlowContext) -> GlRef {
 	Arc::new(gl)
 }
 
+#[cfg(feature = "graphics")]
 pub fn newShaderRef(shader: Shader) -> ShaderRef {
 	Arc::new(RwLock::new(shader))
 }
 
+#[cfg(feature = "graphics")]
 pub fn newRenderableRef<T: Renderable + 'static>(renderable: T) -> RenderableRef {
 	Rc::new(RefCell::new(renderable))
 }
 
+#[cfg(feature = "graphics")]
 pub fn newMeshRef(mesh: Mesh) -> MeshRef {
 	Rc::new(RefCell::new(mesh))
 }
@@ -46,6 +68,30 @@ pub fn newPhysicalRef<P: Physical + 'static>(physical: P) -> PhysicalRef {
 	Arc::new(RwLock::new(physical))
 }
 
+pub fn newConstraintRef<C: Constraint + 'static>(constraint: C) -> ConstraintRef {
+	Arc::new(RwLock::new(constraint))
+}
+
+pub fn newForceFieldRef<F: ForceField + 'static>(field: F) -> ForceFieldRef {
+	Arc::new(RwLock::new(field))
+}
+
+pub fn newObstacleRef(obstacle: Obstacle) -> ObstacleRef {
+	Arc::new(obstacle)
+}
+
+pub fn newSensorRef(sensor: Sensor) -> SensorRef {
+	Arc::new(RwLock::new(sensor))
+}
+
+pub fn newKillZoneRef(zone: KillZone) -> KillZoneRef {
+	Arc::new(RwLock::new(zone))
+}
+
+pub fn newEmitterRef(emitter: Emitter) -> EmitterRef {
+	Arc::new(RwLock::new(emitter))
+}
+
 pub fn newSolverRef(solver: Solver) -> SolverRef {
 	Rc::new(RefCell::new(solver))
 }
\ No newline at end of file
@@ -12,14 +12,14 @@ use sdl3::mouse::MouseButton;
 use sdl3::timer;
 use sdl3::video::{GLContext, GLProfile, SwapInterval};
 use tracing::{info, warn};
-use crate::gl_check_error;
-use crate::graphics::{RenderManager, Renderable};
-use crate::graphics::shaders;
-use crate::simulation::ball::{Ball, BallRenderable};
-use crate::simulation::camera::{screenToWorldSpace, Camera, Frustum, Projection};
-use crate::simulation::{Transform, Solver};
-use crate::types::{newGlRef, newPhysicalRef, newRenderableRef, newSdlWindowRef, newSolverRef, GlRef, SdlWindowRef, SolverRef};
-use crate::window::InputHelper;
+use catbox_rs::gl_check_error;
+use catbox_rs::graphics::{RenderManager, Renderable};
+use catbox_rs::graphics::shaders;
+use catbox_rs::simulation::ball::Ball;
+use catbox_rs::simulation::camera::{screenToWorldSpace, Camera, Frustum, Projection};
+use catbox_rs::simulation::{Transform, Solver, BallRenderable, PolygonRenderable, SolverRenderable};
+use catbox_rs::types::{newGlRef, newPhysicalRef, newRenderableRef, newSolverRef, GlRef, SolverRef};
+use crate::window::{newSdlWindowRef, InputHelper, SdlWindowRef};
 
 const F_RUNNING: u8 = 0;
 
@@ -137,9 +137,9 @@ impl CatBox {
 		let baseShader = shaders::baseShader(gl.clone())?;
 		let instanceShader = shaders::instanceShader(gl.clone())?;
 		
-		let solver = newSolverRef(Solver::new(Vec3::splat(1000.0), gl.clone(), baseShader)?);
+		let solver = newSolverRef(Solver::new(Vec3::splat(1000.0)));
 		let mut renderManager = RenderManager::new(gl.clone())?;
-		renderManager.addRenderable(solver.clone());
+		renderManager.addRenderable(newRenderableRef(SolverRenderable::new(gl.clone(), baseShader.clone(), solver.clone())?));
 		
 		let camera = Camera {
 			frustum: Frustum {
@@ -182,6 +182,10 @@ impl CatBox {
 		let ballRenderable = newRenderableRef(ballRenderable);
 		renderManager.addRenderable(ballRenderable.clone());
 		
+		let polygonRenderable = PolygonRenderable::new(gl.clone(), baseShader.clone(), solver.clone());
+		polygonRenderable.meshRef().unwrap().borrow_mut().upload(baseShader.clone())?;
+		renderManager.addRenderable(newRenderableRef(polygonRenderable));
+		
 		let mut catbox = CatBox {
 			width: WIN_WIDTH,
 			height: WIN_HEIGHT,
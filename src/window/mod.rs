@@ -1,5 +1,15 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use sdl3::video::Window as SdlWindow;
+
 mod catbox;
 mod input_helper;
 
 pub use catbox::CatBox;
 pub use input_helper::InputHelper;
+
+pub type SdlWindowRef = Rc<RefCell<SdlWindow>>;
+
+pub fn newSdlWindowRef(window: SdlWindow) -> SdlWindowRef {
+	Rc::new(RefCell::new(window))
+}
\ No newline at end of file